@@ -0,0 +1,478 @@
+#ifndef AVICALC_H
+#define AVICALC_H
+
+/* Generated with cbindgen from src/ffi.rs, do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define EQUALS_BUTTON 1000
+
+#define BACKSPACE_BUTTON 1001
+
+#define CLEAR_BUTTON 1002
+
+#define UNDO_BUTTON 1003
+
+#define REDO_BUTTON 1004
+
+#define CURSOR_LEFT_BUTTON 1005
+
+#define CURSOR_RIGHT_BUTTON 1006
+
+#define CURSOR_HOME_BUTTON 1007
+
+#define CURSOR_END_BUTTON 1008
+
+#define ANGLE_MODE_BUTTON 1009
+
+#define TIME_DISPLAY_BUTTON 1010
+
+#define ZERO_BUTTON 0
+
+#define ONE_BUTTON 1
+
+#define TWO_BUTTON 2
+
+#define THREE_BUTTON 3
+
+#define FOUR_BUTTON 4
+
+#define FIVE_BUTTON 5
+
+#define SIX_BUTTON 6
+
+#define SEVEN_BUTTON 7
+
+#define EIGHT_BUTTON 8
+
+#define NINE_BUTTON 9
+
+#define PERIOD_BUTTON 10
+
+#define PLUS_BUTTON 11
+
+#define MINUS_BUTTON 12
+
+#define MULTIPLY_BUTTON 13
+
+#define DIVIDE_BUTTON 14
+
+#define POWER_BUTTON 17
+
+#define COMMA_BUTTON 18
+
+#define EXPONENT_BUTTON 19
+
+#define PERCENT_BUTTON 20
+
+#define COLON_BUTTON 27
+
+#define DEGREE_BUTTON 28
+
+#define MINUTE_BUTTON 29
+
+#define SECOND_BUTTON 37
+
+#define NORTH_BUTTON 45
+
+#define SOUTH_BUTTON 46
+
+#define EAST_BUTTON 47
+
+#define WEST_BUTTON 48
+
+#define LEFT_PARENTH_BUTTON 15
+
+#define RIGHT_PARENTH_BUTTON 16
+
+#define SIN_BUTTON 21
+
+#define COS_BUTTON 22
+
+#define TAN_BUTTON 23
+
+#define ARCSIN_BUTTON 24
+
+#define ARCCOS_BUTTON 25
+
+#define ARCTAN_BUTTON 26
+
+#define ANS_BUTTON 30
+
+#define SQRT_BUTTON 31
+
+#define CBRT_BUTTON 32
+
+#define LN_BUTTON 33
+
+#define LOG10_BUTTON 34
+
+#define EXP_BUTTON 35
+
+#define ABS_BUTTON 36
+
+#define ATAN2_BUTTON 71
+
+#define MIN_BUTTON 72
+
+#define MAX_BUTTON 73
+
+#define HYPOT_BUTTON 74
+
+#define A_BUTTON 41
+
+#define B_BUTTON 42
+
+#define C_BUTTON 43
+
+#define D_BUTTON 44
+
+#define TEMP_BUTTON 51
+
+#define ALTITUDE_BUTTON 52
+
+#define ALTIMETER_BUTTON 53
+
+#define WIND_SPEED_BUTTON 54
+
+#define WIND_HEADING_BUTTON 55
+
+#define COURSE_BUTTON 56
+
+#define CALIBRATED_AIRSPEED_BUTTON 57
+
+#define DEW_POINT_BUTTON 58
+
+#define PRESSURE_ALTITUDE_BUTTON 61
+
+#define DENSITY_ALTITUDE_BUTTON 62
+
+#define HEAD_WIND_BUTTON 63
+
+#define CROSS_WIND_BUTTON 64
+
+#define HEADING_BUTTON 65
+
+#define TRUE_AIRSPEED_BUTTON 66
+
+#define GROUND_SPEED_BUTTON 67
+
+#define A_ASSIGN_BUTTON 101
+
+#define B_ASSIGN_BUTTON 102
+
+#define C_ASSIGN_BUTTON 103
+
+#define D_ASSIGN_BUTTON 104
+
+#define TEMP_ASSIGN_BUTTON 111
+
+#define ALTITUDE_ASSIGN_BUTTON 112
+
+#define ALTIMETER_ASSIGN_BUTTON 113
+
+#define WIND_SPEED_ASSIGN_BUTTON 114
+
+#define WIND_HEADING_ASSIGN_BUTTON 115
+
+#define COURSE_ASSIGN_BUTTON 116
+
+#define CALIBRATED_AIRSPEED_ASSIGN_BUTTON 117
+
+#define DEW_POINT_ASSIGN_BUTTON 118
+
+#define A_ADD_BUTTON 201
+
+#define B_ADD_BUTTON 202
+
+#define C_ADD_BUTTON 203
+
+#define D_ADD_BUTTON 204
+
+#define TEMP_ADD_BUTTON 211
+
+#define ALTITUDE_ADD_BUTTON 212
+
+#define ALTIMETER_ADD_BUTTON 213
+
+#define WIND_SPEED_ADD_BUTTON 214
+
+#define WIND_HEADING_ADD_BUTTON 215
+
+#define COURSE_ADD_BUTTON 216
+
+#define CALIBRATED_AIRSPEED_ADD_BUTTON 217
+
+#define DEW_POINT_ADD_BUTTON 218
+
+#define NM_TO_FEET_BUTTON 301
+
+#define FEET_TO_NM_BUTTON 302
+
+#define KNOTS_TO_FPM_BUTTON 303
+
+#define FPM_TO_KNOTS_BUTTON 304
+
+#define KPH_TO_MPM_BUTTON 305
+
+#define MPM_TO_KPH_BUTTON 306
+
+#define NM_TO_MILES_BUTTON 307
+
+#define MILES_TO_NM_BUTTON 308
+
+#define MILES_TO_KILOMETERS_BUTTON 309
+
+#define KILOMETERS_TO_MILES_BUTTON 310
+
+#define KNOTS_TO_MPH_BUTTON 311
+
+#define MPH_TO_KNOTS_BUTTON 312
+
+#define C_TO_F_BUTTON 313
+
+#define F_TO_C_BUTTON 314
+
+#define LBS_TO_KGS_BUTTON 315
+
+#define KGS_TO_LBS_BUTTON 316
+
+#define LITERS_TO_GALLONS_BUTTON 317
+
+#define GALLONS_TO_LITTERS_BUTTON 318
+
+#define GALLONS_TO_AVIGAS_LBS_BUTTON 319
+
+#define GALLONS_TO_JET_FUEL_LBS_BUTTON 320
+
+#define INHG_TO_HECTOPASCALS_BUTTON 321
+
+#define HECTOPASCALS_TO_INHG_BUTTON 322
+
+#define FEET_TO_METERS 323
+
+#define METERS_TO_FEET 324
+
+#define A_FORMULA_BUTTON 401
+
+#define B_FORMULA_BUTTON 402
+
+#define C_FORMULA_BUTTON 403
+
+#define D_FORMULA_BUTTON 404
+
+#define NAUTICAL_MILE_UNIT_BUTTON 501
+
+#define MILE_UNIT_BUTTON 502
+
+#define KILOMETER_UNIT_BUTTON 503
+
+#define FOOT_UNIT_BUTTON 504
+
+#define METER_UNIT_BUTTON 505
+
+#define KNOT_UNIT_BUTTON 506
+
+#define FEET_PER_MINUTE_UNIT_BUTTON 507
+
+#define MILES_PER_HOUR_UNIT_BUTTON 508
+
+#define KILOMETERS_PER_HOUR_UNIT_BUTTON 509
+
+#define METERS_PER_MINUTE_UNIT_BUTTON 510
+
+#define LITER_UNIT_BUTTON 511
+
+#define GALLON_UNIT_BUTTON 512
+
+#define KILOGRAM_UNIT_BUTTON 513
+
+#define POUND_UNIT_BUTTON 514
+
+#define CELCIUS_UNIT_BUTTON 515
+
+#define KELVIN_UNIT_BUTTON 516
+
+#define FAHRENHEIT_UNIT_BUTTON 517
+
+#define AVIGAS_POUND_UNIT_BUTTON 518
+
+#define JETFUEL_POUND_UNIT_BUTTON 519
+
+#define INHG_UNIT_BUTTON 520
+
+#define KILOPASCAL_UNIT_BUTTON 521
+
+#define HECTOPASCAL_UNIT_BUTTON 522
+
+#define PASCAL_UNIT_BUTTON 523
+
+#define HOUR_UNIT_BUTTON 524
+
+#define STATE_DOCUMENT_VERSION 1
+
+#define NAME_LENGTH 16
+
+#define NEGATION_PRECEDENCE 3
+
+#define TAPE_LENGTH 50
+
+#define UNDO_LEVELS 100
+
+typedef enum AvicalcErrorKind {
+  AVICALC_ERROR_KIND_MISMATCHED_PARENTHESIS,
+  AVICALC_ERROR_KIND_MALFORMED_NUMBER,
+  AVICALC_ERROR_KIND_DOMAIN_ERROR,
+  AVICALC_ERROR_KIND_DIVISION_BY_ZERO,
+  AVICALC_ERROR_KIND_EMPTY_STACK,
+  AVICALC_ERROR_KIND_MALFORMED_EXPRESSION,
+  AVICALC_ERROR_KIND_CIRCULAR_FORMULA,
+  AVICALC_ERROR_KIND_UNDEFINED_VARIABLE,
+  AVICALC_ERROR_KIND_DIMENSION_MISMATCH,
+} AvicalcErrorKind;
+
+enum AvicalcAngleMode
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : int32_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  AVICALC_ANGLE_MODE_DEGREES,
+  AVICALC_ANGLE_MODE_RADIANS,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum AvicalcAngleMode AvicalcAngleMode;
+#else
+typedef int32_t AvicalcAngleMode;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+enum AvicalcDisplayMode
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : int32_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  AVICALC_DISPLAY_MODE_DECIMAL,
+  AVICALC_DISPLAY_MODE_TIME,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum AvicalcDisplayMode AvicalcDisplayMode;
+#else
+typedef int32_t AvicalcDisplayMode;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+enum AvicalcVariable
+#if defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+  : int32_t
+#endif // defined(__cplusplus) || __STDC_VERSION__ >= 202311L
+ {
+  AVICALC_VARIABLE_ANS,
+  AVICALC_VARIABLE_PREV_ANS,
+  AVICALC_VARIABLE_A,
+  AVICALC_VARIABLE_B,
+  AVICALC_VARIABLE_C,
+  AVICALC_VARIABLE_D,
+  AVICALC_VARIABLE_CAS,
+  AVICALC_VARIABLE_TAS,
+  AVICALC_VARIABLE_ALTITUDE,
+  AVICALC_VARIABLE_ALTIMETER,
+  AVICALC_VARIABLE_TEMP,
+  AVICALC_VARIABLE_DENS_ALT,
+  AVICALC_VARIABLE_PRESS_ALT,
+  AVICALC_VARIABLE_WIND_DIR,
+  AVICALC_VARIABLE_WIND_SPEED,
+  AVICALC_VARIABLE_COURSE,
+  AVICALC_VARIABLE_HEAD_WIND,
+  AVICALC_VARIABLE_CROSS_WIND,
+  AVICALC_VARIABLE_HEADING,
+  AVICALC_VARIABLE_GRD_SPD,
+  AVICALC_VARIABLE_DEW_POINT,
+};
+#ifndef __cplusplus
+#if __STDC_VERSION__ >= 202311L
+typedef enum AvicalcVariable AvicalcVariable;
+#else
+typedef int32_t AvicalcVariable;
+#endif // __STDC_VERSION__ >= 202311L
+#endif // __cplusplus
+
+typedef struct AvicalcCalculator AvicalcCalculator;
+
+typedef struct AvicalcCallbacks {
+  void *user_data;
+  void (*entry_changed)(void *user_data, const char *entry);
+  void (*history_changed)(void *user_data, const char *previous_entry);
+  void (*variable_changed)(void *user_data, int32_t variable, double value);
+  void (*error)(void *user_data, enum AvicalcErrorKind kind, size_t token_index);
+  void (*cursor_moved)(void *user_data, size_t position);
+  void (*angle_mode_changed)(void *user_data, AvicalcAngleMode mode);
+  void (*display_mode_changed)(void *user_data, AvicalcDisplayMode mode);
+  void (*tape_changed)(void *user_data,
+                       size_t length,
+                       const char *last_expression,
+                       double last_result);
+  void (*formula_changed)(void *user_data, int32_t variable, const char *formula);
+  void (*named_variable_changed)(void *user_data, const char *name, const double *value);
+} AvicalcCallbacks;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a calculator with default values and no callbacks. Free it with avicalc_free.
+ */
+struct AvicalcCalculator *avicalc_new(void);
+
+/**
+ * Frees a calculator created with avicalc_new.
+ *
+ * # Safety
+ * calculator must be NULL or come from avicalc_new and not be freed yet.
+ */
+void avicalc_free(struct AvicalcCalculator *calculator);
+
+/**
+ * Replaces the callbacks used to update the screen.
+ *
+ * # Safety
+ * calculator must come from avicalc_new and not be freed yet.
+ */
+void avicalc_set_callbacks(struct AvicalcCalculator *calculator, struct AvicalcCallbacks callbacks);
+
+/**
+ * Presses the key with the given button number, see the *_BUTTON constants. Returns 0 on
+ * success and -1 if the button number is unknown.
+ *
+ * # Safety
+ * calculator must come from avicalc_new and not be freed yet.
+ */
+int32_t avicalc_press_button(struct AvicalcCalculator *calculator, uint16_t button);
+
+/**
+ * Calls every callback with the current contents of the screen.
+ *
+ * # Safety
+ * calculator must come from avicalc_new and not be freed yet.
+ */
+void avicalc_refresh(struct AvicalcCalculator *calculator);
+
+/**
+ * Returns the value of a variable, see AvicalcVariable. Unknown variables give NAN.
+ *
+ * # Safety
+ * calculator must come from avicalc_new and not be freed yet.
+ */
+double avicalc_get_variable(const struct AvicalcCalculator *calculator, int32_t variable);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* AVICALC_H */
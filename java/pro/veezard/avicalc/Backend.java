@@ -0,0 +1,120 @@
+package pro.veezard.avicalc;
+
+// Java side of src/jni_bindings.rs. Build the library with `cargo build --features jni` and load
+// it before creating a Backend, e.g. with System.loadLibrary("avicalc_backend").
+public final class Backend implements AutoCloseable {
+    // Receives every change of the calculator screen, on the thread that pressed the key
+    public interface Listener {
+        void entryChanged(String entry);
+
+        // position is the number of characters of the entry field before the cursor
+        default void cursorMoved(int position) {}
+
+        // angleMode is ANGLE_MODE_DEGREES or ANGLE_MODE_RADIANS
+        default void angleModeChanged(int angleMode) {}
+
+        // displayMode is DISPLAY_MODE_DECIMAL or DISPLAY_MODE_TIME, in which results are shown
+        // as H:MM:SS
+        default void displayModeChanged(int displayMode) {}
+
+        void historyChanged(String previousEntry);
+
+        // variable is the ordinal of the variable, see the VARIABLE_* constants, which follow
+        // the Variable enum of src/objects.rs
+        void variableChanged(int variable, double value);
+
+        // The entry field can't be evaluated, tokenIndex points at the offending token
+        void error(String message, int tokenIndex);
+
+        // A calculation was added to the tape or the tape was cleared, lastExpression is null if
+        // the tape is empty
+        default void tapeChanged(int length, String lastExpression, double lastResult) {}
+
+        // formula is null when the formula of the variable was removed
+        default void formulaChanged(int variable, String formula) {}
+
+        // value is null when the user-named variable was deleted
+        default void namedVariableChanged(String name, Double value) {}
+    }
+
+    public static final int ANGLE_MODE_DEGREES = 0;
+    public static final int ANGLE_MODE_RADIANS = 1;
+
+    public static final int DISPLAY_MODE_DECIMAL = 0;
+    public static final int DISPLAY_MODE_TIME = 1;
+
+    public static final int VARIABLE_ANS = 0;
+    public static final int VARIABLE_PREV_ANS = 1;
+    public static final int VARIABLE_A = 2;
+    public static final int VARIABLE_B = 3;
+    public static final int VARIABLE_C = 4;
+    public static final int VARIABLE_D = 5;
+    public static final int VARIABLE_CAS = 6;
+    public static final int VARIABLE_TAS = 7;
+    public static final int VARIABLE_ALTITUDE = 8;
+    public static final int VARIABLE_ALTIMETER = 9;
+    public static final int VARIABLE_TEMP = 10;
+    public static final int VARIABLE_DENS_ALT = 11;
+    public static final int VARIABLE_PRESS_ALT = 12;
+    public static final int VARIABLE_WIND_DIR = 13;
+    public static final int VARIABLE_WIND_SPEED = 14;
+    public static final int VARIABLE_COURSE = 15;
+    public static final int VARIABLE_HEAD_WIND = 16;
+    public static final int VARIABLE_CROSS_WIND = 17;
+    public static final int VARIABLE_HEADING = 18;
+    public static final int VARIABLE_GRD_SPD = 19;
+    public static final int VARIABLE_DEW_POINT = 20;
+
+    private long handle;
+
+    public Backend(Listener listener) {
+        handle = create(listener);
+    }
+
+    // Presses the key with the given button number, see src/consts.rs. Returns false if the
+    // button number is unknown.
+    public boolean keyPressed(int button) {
+        return keyPressed(handle, button);
+    }
+
+    // Calls the listener with the current contents of the screen
+    public void refresh() {
+        refresh(handle);
+    }
+
+    // Returns NaN for unknown variables
+    public double getVariable(int variable) {
+        return getVariable(handle, variable);
+    }
+
+    // Returns the whole state as a JSON document
+    public String saveState() {
+        return saveState(handle);
+    }
+
+    // Replaces the state by a document from saveState and calls the listener with the new
+    // contents of the screen. Throws an IllegalArgumentException if the document can't be read.
+    public void restoreState(String saved) {
+        restoreState(handle, saved);
+    }
+
+    @Override
+    public void close() {
+        destroy(handle);
+        handle = 0;
+    }
+
+    private static native long create(Listener listener);
+
+    private static native void destroy(long handle);
+
+    private static native boolean keyPressed(long handle, int button);
+
+    private static native void refresh(long handle);
+
+    private static native double getVariable(long handle, int variable);
+
+    private static native String saveState(long handle);
+
+    private static native void restoreState(long handle, String saved);
+}
@@ -1,78 +1,65 @@
 use super::*;
 use crate::computation::*;
-use crate::consts::*;
+use crate::display::*;
+use crate::formulas::*;
 use crate::objects::*;
 use crate::variable_computations::*;
 
-use std::cell::RefCell;
-pub fn assign_pressed(
-    key: u16,
-    state: &mut Box<State>,
-    string_writers: &mut Vec<RefCell<Box<dyn FnMut(String) -> ()>>>,
-) -> () {
-    let value_to_assign: f64;
-    if !state.entry_field.is_empty() {
-        let result = compute_entry_field(state);
-        match result {
-            Err(err) => {
-                string_writers[ENTRY_FIELD_WRITER].borrow_mut()(err);
-                return ();
-            }
-            Ok(answer) => {
-                state.entry_field.clear();
-                state
-                    .variable_values
-                    .set(Variable::PrevAns, state.variable_values.get(Variable::Ans));
-                state.variable_values.set(Variable::Ans, answer);
-                update_string_field(state, string_writers, ENTRY_FIELD_WRITER);
-                value_to_assign = answer;
-            }
-        }
-    } else {
-        value_to_assign = state.variable_values.get(Variable::Ans);
-    }
-    match key {
-        button_number => {
-            let variable = button_number_to_token(button_number).to_inner_var().expect("A non-variable token is attempted to be assigned to. Probably a mistake in button_number_to_token function");
-            state.variable_values.set(variable, value_to_assign);
-            update_unassignable_quantities(state);
-        }
+// The value the assign keys use: the entry field in the unit, which then becomes Ans, or Ans if
+// the entry field is empty. None if the entry field can't be evaluated, the error is shown.
+fn value_to_assign(
+    unit: Option<Unit>,
+    state: &mut State,
+    sink: &mut dyn DisplaySink,
+) -> Option<f64> {
+    if state.entry_field.is_empty() {
+        return Some(state.variable_values.get(Variable::Ans));
     }
-}
-
-pub fn add_to_var_pressed(
-    key: u16,
-    state: &mut Box<State>,
-    string_writers: &mut Vec<RefCell<Box<dyn FnMut(String) -> ()>>>,
-) -> () {
-    let value_to_add: f64;
-    if !state.entry_field.is_empty() {
-        let result = compute_entry_field(state);
-        match result {
-            Err(err) => {
-                string_writers[ENTRY_FIELD_WRITER].borrow_mut()(err);
-                return ();
-            }
-            Ok(answer) => {
-                state.entry_field.clear();
-                state
-                    .variable_values
-                    .set(Variable::PrevAns, state.variable_values.get(Variable::Ans));
-                state.variable_values.set(Variable::Ans, answer);
-                update_string_field(state, string_writers, ENTRY_FIELD_WRITER);
-                value_to_add = answer;
-            }
+    match compute_entry_field_in_unit(unit, state) {
+        Err(err) => {
+            sink.error(err);
+            return None;
         }
-    } else {
-        value_to_add = state.variable_values.get(Variable::Ans);
-    }
-    match key {
-        button_number => {
-            let variable = button_number_to_token(button_number).to_inner_var().expect("A non-variable token is attempted to be assigned to. Probably a mistake in button_number_to_token function");
+        Ok(answer) => {
+            state.entry_field.clear();
+            state.cursor = 0;
             state
                 .variable_values
-                .set(variable, value_to_add + state.variable_values.get(variable));
-            update_unassignable_quantities(state);
+                .set(Variable::PrevAns, state.variable_values.get(Variable::Ans));
+            state.variable_values.set(Variable::Ans, answer);
+            show_entry_field(state, sink);
+            return Some(answer);
         }
     }
 }
+
+pub fn assign_pressed(variable: Variable, state: &mut State, sink: &mut dyn DisplaySink) -> () {
+    let value = match value_to_assign(variable.unit(), state, sink) {
+        Some(value) => value,
+        None => return (),
+    };
+    remove_formula(variable, state, sink);
+    state.variable_values.set(variable, value);
+    update_unassignable_quantities(state);
+}
+
+pub fn assign_named_pressed(name: Name, state: &mut State, sink: &mut dyn DisplaySink) -> () {
+    let value = match value_to_assign(None, state, sink) {
+        Some(value) => value,
+        None => return (),
+    };
+    state.named_values.insert(name, value);
+    sink.named_variable_changed(name.as_str(), Some(value));
+}
+
+pub fn add_to_var_pressed(variable: Variable, state: &mut State, sink: &mut dyn DisplaySink) -> () {
+    let value_to_add = match value_to_assign(variable.unit(), state, sink) {
+        Some(value) => value,
+        None => return (),
+    };
+    remove_formula(variable, state, sink);
+    state
+        .variable_values
+        .set(variable, value_to_add + state.variable_values.get(variable));
+    update_unassignable_quantities(state);
+}
@@ -0,0 +1,227 @@
+// Evaluates expressions line by line, e.g. to script density altitude and TAS checks:
+//
+//     avicalc --set alt=5500 --set tmp=30 checks.txt
+//
+// Every line is either an expression, whose result is printed, or an assignment "name = expr"
+// into a variable that has an assign key, e.g. "Cas = 120" or "a = 2*ans", or into a variable
+// with a name of your own, e.g. "fuel_left = 42". Empty lines and lines starting with # are
+// skipped. Results are printed in the display mode of the scenario, e.g. 1:30:00 in the time mode.
+#![allow(clippy::needless_return, clippy::unused_unit)]
+extern crate avicalc_backend;
+
+use avicalc_backend::display::*;
+use avicalc_backend::error::*;
+use avicalc_backend::key::*;
+use avicalc_backend::objects::*;
+use avicalc_backend::Calculator;
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::process;
+use strum::IntoEnumIterator;
+
+const USAGE: &str = "usage: avicalc [--scenario FILE] [--set NAME=EXPR]... [FILE]
+
+Reads expressions, one per line, from FILE or the standard input and prints their results.
+Lines of the form NAME = EXPR assign the result to a variable, which is created if NAME isn't
+one of the calculator's.
+
+  --scenario FILE   start from a saved state, a .json or .toml document
+  --set NAME=EXPR   assign a variable before reading the expressions";
+
+// Remembers the last error of the calculator
+#[derive(Default)]
+struct ErrorSink {
+    error: Option<CalcError>,
+}
+
+impl DisplaySink for ErrorSink {
+    fn error(&mut self, err: CalcError) -> () {
+        self.error = Some(err);
+    }
+}
+
+// Accepts the names shown on the calculator, e.g. "tmp", as well as the names of the variables,
+// e.g. "Temp"
+fn variable_from_name(name: &str) -> Option<Variable> {
+    return Variable::iter().find(|var| {
+        name.eq_ignore_ascii_case(&var.show()) || name.eq_ignore_ascii_case(&format!("{:?}", var))
+    });
+}
+
+// Returns what to print for the line, if anything
+fn run_line(calculator: &mut Calculator<ErrorSink>, line: &str) -> Result<Option<String>, String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return Ok(None);
+    }
+    let (target, expression) = match line.find('=') {
+        Some(position) => {
+            let name = line[..position].trim();
+            let key = match variable_from_name(name) {
+                Some(var) => Key::Assign(var),
+                None => {
+                    Key::AssignNamed(Name::new(name).map_err(|err| format!("{}: {}", name, err))?)
+                }
+            };
+            if matches!(key, Key::Assign(_)) && u16::try_from(key).is_err() {
+                return Err(format!("{} can't be assigned, it is computed", name));
+            }
+            (Some((name, key)), &line[position + 1..])
+        }
+        None => (None, line),
+    };
+
+    calculator.press(Key::Clear);
+    calculator.sink_mut().error = None;
+    calculator
+        .paste(expression)
+        .map_err(|err| format!("{} in {:?}", err, expression.trim()))?;
+    match target {
+        Some((_, key)) => calculator.press(key),
+        None => calculator.press(Key::Equals),
+    }
+    if let Some(err) = calculator.sink().error {
+        return Err(format!(
+            "{} in {:?} at token {}",
+            err,
+            calculator.entry_field_as_str(),
+            err.index()
+        ));
+    }
+    // Shown the way the calculator shows them, e.g. as H:MM:SS in the time display mode
+    let mode = calculator.state().display_mode;
+    return Ok(Some(match target {
+        Some((name, Key::Assign(var))) => {
+            format!("{} = {}", name, mode.format(calculator.variable(var)))
+        }
+        Some((name, _)) => format!(
+            "{} = {}",
+            name,
+            mode.format(calculator.named_variable(name).unwrap())
+        ),
+        None => mode.format(calculator.variable(Variable::Ans)),
+    }));
+}
+
+fn load_scenario(path: &str) -> Result<State, String> {
+    let text = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+    let state = if path.ends_with(".toml") {
+        State::from_toml(&text)
+    } else {
+        State::from_json(&text)
+    };
+    return state.map_err(|err| format!("{}: {}", path, err));
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("avicalc: {}", message);
+    process::exit(2);
+}
+
+fn main() {
+    let mut scenario = None;
+    let mut assignments = Vec::new();
+    let mut input = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            "--scenario" => scenario = Some(args.next().unwrap_or_else(|| fail(USAGE))),
+            "--set" => assignments.push(args.next().unwrap_or_else(|| fail(USAGE))),
+            _ if arg.starts_with("--") => fail(USAGE),
+            _ if input.is_none() => input = Some(arg),
+            _ => fail(USAGE),
+        }
+    }
+
+    let state = match scenario {
+        Some(path) => load_scenario(&path).unwrap_or_else(|err| fail(&err)),
+        None => State::default(),
+    };
+    let mut calculator = Calculator::new(state, ErrorSink::default());
+    for assignment in assignments.iter() {
+        if !assignment.contains('=') {
+            fail(&format!("--set {}: expected NAME=EXPR", assignment));
+        }
+        if let Err(err) = run_line(&mut calculator, assignment) {
+            fail(&format!("--set {}: {}", assignment, err));
+        }
+    }
+
+    let reader: Box<dyn BufRead> = match input {
+        Some(path) => match File::open(&path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(err) => fail(&format!("{}: {}", path, err)),
+        },
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+    let mut failed = false;
+    for (number, line) in reader.lines().enumerate() {
+        let line = line.unwrap_or_else(|err| fail(&err.to_string()));
+        match run_line(&mut calculator, &line) {
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => (),
+            Err(err) => {
+                eprintln!("line {}: {}", number + 1, err);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod test_avicalc {
+    use super::*;
+
+    #[test]
+    fn test_lines() {
+        let mut calculator = Calculator::new(State::default(), ErrorSink::default());
+        let mut run = |line: &str| run_line(&mut calculator, line);
+        assert_eq!(run("# comment"), Ok(None));
+        assert_eq!(run("Cas = 100 + 20"), Ok(Some("Cas = 120".to_string())));
+        assert_eq!(run("cas / 2"), Ok(Some("60".to_string())));
+        assert_eq!(run("A = ans * 2"), Ok(Some("A = 120".to_string())));
+        assert_eq!(
+            run("dal = 3"),
+            Err("dal can't be assigned, it is computed".to_string())
+        );
+        assert_eq!(
+            run("fuel_left = 40+2"),
+            Ok(Some("fuel_left = 42".to_string()))
+        );
+        assert_eq!(run("fuel_left / 2"), Ok(Some("21".to_string())));
+        assert_eq!(
+            run("2x = 3"),
+            Err("2x: names start with a letter followed by letters, digits and _".to_string())
+        );
+        assert_eq!(
+            run("1 / (2 - 2)"),
+            Err("Can't divide by 0 in \"1/(2-2)\" at token 1".to_string())
+        );
+        assert!(run("2 + x").is_err());
+    }
+
+    #[test]
+    fn test_time_display_mode() {
+        let state = State {
+            display_mode: DisplayMode::Time,
+            ..Default::default()
+        };
+        let mut calculator = Calculator::new(state, ErrorSink::default());
+        let mut run = |line: &str| run_line(&mut calculator, line);
+        assert_eq!(run("1:30 + 0:45"), Ok(Some("2:15:00".to_string())));
+        assert_eq!(
+            run("A = 90 nm / 120 kt"),
+            Ok(Some("A = 0:45:00".to_string()))
+        );
+        assert_eq!(run("leg = 1.5"), Ok(Some("leg = 1:30:00".to_string())));
+    }
+}
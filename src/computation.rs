@@ -1,102 +1,226 @@
 use super::*;
-use crate::consts::*;
+use crate::display::*;
+use crate::error::*;
 use crate::objects::*;
+use crate::tape::*;
 
-use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::result::Result;
+use std::time::SystemTime;
 
-pub fn compute_entry_field(state: &mut Box<State>) -> Result<f64, String> {
+// Tokens carry their index in the entry field so that errors can point at the offending token
+type IndexedToken = (usize, Token);
+
+pub fn compute_entry_field(state: &mut State) -> Result<f64, CalcError> {
+    return compute_entry_field_quantity(state).map(|quantity| quantity.value);
+}
+
+// The result with its unit, e.g. to convert it into the unit of a variable
+pub fn compute_entry_field_quantity(state: &mut State) -> Result<Quantity, CalcError> {
+    let entry_field = (*state.entry_field).clone();
+    return compute_quantity(&entry_field, state);
+}
+
+// The entry field converted into a unit, e.g. 100 kph for cas. A number without a unit is taken
+// to be in the unit. A mismatch points at the last token that gives the result a unit.
+pub fn compute_entry_field_in_unit(
+    unit: Option<Unit>,
+    state: &mut State,
+) -> Result<f64, CalcError> {
+    let quantity = compute_entry_field_quantity(state)?;
+    let unit = match unit {
+        Some(unit) => unit,
+        None => return Ok(quantity.value),
+    };
+    let index = state
+        .entry_field
+        .iter()
+        .rposition(|tok| match tok {
+            Token::Unit(_) | Token::Digit(Digit::Colon) => true,
+            Token::Variable(var) => var.unit().is_some(),
+            _ => false,
+        })
+        .unwrap_or(0);
+    return quantity
+        .in_unit(unit)
+        .map(|quantity| quantity.value)
+        .ok_or(CalcError::DimensionMismatch { index });
+}
+
+// Evaluates tokens that aren't in the entry field, e.g. a formula. The indices of errors point
+// into the expression.
+pub fn compute_expression(
+    expression: &VecDeque<Token>,
+    state: &mut State,
+) -> Result<f64, CalcError> {
+    return compute_quantity(expression, state).map(|quantity| quantity.value);
+}
+
+pub fn compute_quantity(
+    expression: &VecDeque<Token>,
+    state: &mut State,
+) -> Result<Quantity, CalcError> {
     /*Implements shunting yard algorithm */
-    let rpn_notation_maybe: Result<VecDeque<Token>, String> = entry_field_to_rpn(state);
+    let rpn_notation_maybe: Result<VecDeque<IndexedToken>, CalcError> =
+        entry_field_to_rpn(expression, state);
     if let Err(err) = rpn_notation_maybe {
         return Err(err);
     }
     let mut rpn_notation = rpn_notation_maybe.unwrap();
 
-    match evaluate_rpn(&mut rpn_notation) {
+    match evaluate_rpn(&mut rpn_notation, state.angle_mode) {
         Err(err) => {
             return Err(err);
         }
         Ok(answer) => Ok(answer),
     }
 }
-pub fn equals_pressed(
-    state: &mut Box<State>,
-    string_writers: &mut Vec<RefCell<Box<dyn FnMut(String) -> ()>>>,
-) {
+pub fn equals_pressed(state: &mut State, sink: &mut dyn DisplaySink) {
     let result = compute_entry_field(state);
     match result {
-        Err(err) => string_writers[ENTRY_FIELD_WRITER].borrow_mut()(err),
+        Err(err) => sink.error(err),
         Ok(answer) => {
             state
                 .variable_values
                 .set(Variable::PrevAns, state.variable_values.get(Variable::Ans));
             state.variable_values.set(Variable::Ans, answer);
+            state.tape.push(TapeEntry {
+                expression: state.entry_field.iter().cloned().collect(),
+                result: answer,
+                timestamp: SystemTime::now(),
+            });
             state.previous_entry_field = state.entry_field.clone();
             state.entry_field.clear();
-            update_string_field(state, string_writers, ENTRY_FIELD_WRITER);
-            update_string_field(state, string_writers, PREVIOUS_ENTRY_FIELD_WRITER);
+            state.cursor = 0;
+            show_entry_field(state, sink);
+            show_previous_entry_field(state, sink);
+            sink.tape_changed(&state.tape);
         }
     }
 }
 /*rpn stands for reverse polish notation*/
 //This function also substitutes constants.
-fn entry_field_to_rpn(state: &mut Box<State>) -> Result<VecDeque<Token>, String> {
-    let mut rpn_notation: VecDeque<Token> = VecDeque::new();
+fn entry_field_to_rpn(
+    expression: &VecDeque<Token>,
+    state: &mut State,
+) -> Result<VecDeque<IndexedToken>, CalcError> {
+    let mut rpn_notation: VecDeque<IndexedToken> = VecDeque::new();
 
-    let mut operators_stack: Vec<Token> = Vec::new();
-    let mut entry_field = state.entry_field.clone();
+    let mut operators_stack: Vec<IndexedToken> = Vec::new();
+    let mut entry_field: VecDeque<IndexedToken> = expression.iter().cloned().enumerate().collect();
 
     'outer: loop {
         if entry_field.is_empty() {
             loop {
-                if operators_stack.is_empty() {
-                    break 'outer;
-                } else {
-                    rpn_notation.push_back(
-                        operators_stack
-                            .pop()
-                            .ok_or("popping empty operator field".to_string())?,
-                    );
+                match operators_stack.pop() {
+                    None => break 'outer,
+                    Some((index, Token::Parenth(_))) => {
+                        return Err(CalcError::MismatchedParenthesis { index });
+                    }
+                    Some(op) => rpn_notation.push_back(op),
                 }
             }
         } else {
-            let temp_token1: Token = entry_field
-                .pop_front()
-                .ok_or("popping empty entry field".to_string())?;
-            match temp_token1 {
+            let temp_token1: IndexedToken = entry_field.pop_front().unwrap();
+            let index = temp_token1.0;
+            match temp_token1.1 {
+                // A time like 1:30 is in hours
                 Token::Digit(_) => {
                     entry_field.push_front(temp_token1);
-                    rpn_notation.push_back(Token::Num(digits_to_float(&mut entry_field)?));
+                    let is_time = entry_field
+                        .iter()
+                        .map_while(|(_, tok)| match tok {
+                            Token::Digit(digit) => Some(*digit),
+                            _ => None,
+                        })
+                        .any(|digit| digit == Digit::Colon);
+                    rpn_notation.push_back((index, Token::Num(digits_to_float(&mut entry_field)?)));
+                    if is_time {
+                        rpn_notation.push_back((index, Token::Unit(Unit::Hour)));
+                    }
                 }
                 Token::Num(_) => rpn_notation.push_back(temp_token1),
                 Token::Parenth(Parenth::Left) => operators_stack.push(temp_token1),
                 Token::Parenth(Parenth::Right) => loop {
-                    if operators_stack.is_empty() {
-                        return Err("mismatched parentheses".to_string());
-                    }
-                    let temp_token2: Token = operators_stack
+                    let temp_token2: IndexedToken = operators_stack
                         .pop()
-                        .ok_or("popping empty operator stack".to_string())?;
-                    match temp_token2 {
+                        .ok_or(CalcError::MismatchedParenthesis { index })?;
+                    match temp_token2.1 {
                         Token::Parenth(Parenth::Right) => {
-                            return Err("mismatched parentheses".to_string());
+                            return Err(CalcError::MismatchedParenthesis { index });
                         }
                         Token::Parenth(Parenth::Left) => break,
-                        op => rpn_notation.push_back(op),
+                        _ => rpn_notation.push_back(temp_token2),
                     }
                 },
-                Token::Variable(var) => rpn_notation.push_back(Token::Num(var.substitute(state))),
+                // Values of variables are in the unit of the variable
+                Token::Variable(var) => {
+                    rpn_notation.push_back((index, Token::Num(var.substitute(state))));
+                    if let Some(unit) = var.unit() {
+                        rpn_notation.push_back((index, Token::Unit(unit)));
+                    }
+                }
+                // A unit binds tighter than any operator, it applies to the operand before it. After
+                // the parentheses of a function call, that is the result of the function.
+                Token::Unit(_) => {
+                    let after_parenthesis = index.checked_sub(1).is_some_and(|previous| {
+                        expression[previous] == Token::Parenth(Parenth::Right)
+                    });
+                    if after_parenthesis
+                        && matches!(operators_stack.last(), Some((_, Token::Func(_))))
+                    {
+                        rpn_notation.push_back(operators_stack.pop().unwrap());
+                    }
+                    rpn_notation.push_back(temp_token1);
+                }
+                // A coordinate, north and east are positive
+                Token::Hemisphere(hemisphere) => {
+                    if !matches!(entry_field.front(), Some((_, Token::Digit(_)))) {
+                        return Err(CalcError::MalformedNumber { index });
+                    }
+                    let degrees = digits_to_float(&mut entry_field)?;
+                    if degrees > hemisphere.max_degrees() {
+                        return Err(CalcError::DomainError { index });
+                    }
+                    rpn_notation.push_back((index, Token::Num(hemisphere.sign() * degrees)));
+                }
+                Token::Named(name) => match state.named_values.get(&name) {
+                    Some(value) => rpn_notation.push_back((index, Token::Num(*value))),
+                    None => return Err(CalcError::UndefinedVariable { index }),
+                },
                 Token::Func(_) => operators_stack.push(temp_token1),
+                // After a plus or minus the percentage is taken of the number before it, as on
+                // handheld calculators, otherwise it is just divided by 100
+                Token::Percent => match operators_stack.last() {
+                    Some((_, Token::Operator(Operator::Plus)))
+                    | Some((_, Token::Operator(Operator::Minus))) => {
+                        rpn_notation.push_back(temp_token1)
+                    }
+                    _ => {
+                        rpn_notation.push_back((index, Token::Num(100.)));
+                        rpn_notation.push_back((index, Token::Operator(Operator::Divide)));
+                    }
+                },
+                // Evaluates the argument before the comma, up to the parenthesis of the function
+                Token::Comma => loop {
+                    match operators_stack.last() {
+                        Some((_, Token::Parenth(Parenth::Left))) => break,
+                        Some(_) => rpn_notation.push_back(operators_stack.pop().unwrap()),
+                        None => return Err(CalcError::MalformedExpression { index }),
+                    }
+                },
                 Token::Operator(op) => {
                     if operators_stack.is_empty() {
                         operators_stack.push(temp_token1)
                     } else {
-                        let temp_token2: Token = operators_stack
-                            .pop()
-                            .ok_or("poppoing empty operator stack".to_string())?;
-                        match temp_token2 {
+                        let temp_token2: IndexedToken = operators_stack.pop().unwrap();
+                        match temp_token2.1 {
+                            Token::Func(Func::Negation)
+                                if op.precedence() > NEGATION_PRECEDENCE =>
+                            {
+                                operators_stack.push(temp_token2);
+                                operators_stack.push(temp_token1);
+                            }
                             Token::Func(_) => {
                                 rpn_notation.push_back(temp_token2);
                                 entry_field.push_front(temp_token1);
@@ -106,25 +230,21 @@ fn entry_field_to_rpn(state: &mut Box<State>) -> Result<VecDeque<Token>, String>
                                 operators_stack.push(temp_token1);
                             }
                             Token::Operator(stack_operator) => {
-                                if op.precedence() > stack_operator.precedence() {
-                                    operators_stack.push(temp_token2);
-                                    operators_stack.push(temp_token1);
-                                } else if op.precedence() < stack_operator.precedence() {
+                                // The operator on the stack is evaluated first if it binds
+                                // tighter, or as tight and the operators are left-associative
+                                if stack_operator.precedence() > op.precedence()
+                                    || (stack_operator.precedence() == op.precedence()
+                                        && op.associativity() == Associativity::Left)
+                                {
                                     rpn_notation.push_back(temp_token2);
                                     entry_field.push_front(temp_token1);
                                 } else {
-                                    if op != stack_operator {
-                                        panic!("no two operators should have same precedence");
-                                    } else if op.associativity() == Associativity::Left {
-                                        rpn_notation.push_back(temp_token2);
-                                        entry_field.push_front(temp_token1);
-                                    } else if op.associativity() == Associativity::Right {
-                                        operators_stack.push(temp_token2);
-                                        operators_stack.push(temp_token1);
-                                    }
+                                    operators_stack.push(temp_token2);
+                                    operators_stack.push(temp_token1);
                                 }
                             }
-                            _ => panic!("operator stack has nonoperators"),
+                            // Only functions, parentheses and operators are put on the stack
+                            _ => return Err(CalcError::MalformedExpression { index }),
                         }
                     }
                 }
@@ -133,77 +253,723 @@ fn entry_field_to_rpn(state: &mut Box<State>) -> Result<VecDeque<Token>, String>
     }
     return Ok(rpn_notation);
 }
-fn evaluate_rpn(rpn_notation: &mut VecDeque<Token>) -> Result<f64, String> {
+fn evaluate_rpn(
+    rpn_notation: &mut VecDeque<IndexedToken>,
+    angle_mode: AngleMode,
+) -> Result<Quantity, CalcError> {
     /*destroys rpn queue*/
     if rpn_notation.is_empty() {
-        return Ok(0.0);
+        return Ok(Quantity::number(0.0));
     };
-    let mut num_stack: Vec<f64> = Vec::new();
+    let mut num_stack: Vec<Quantity> = Vec::new();
+    let mut last_index: usize = 0;
 
     loop {
         if rpn_notation.is_empty() {
             if num_stack.len() != 1 {
-                return Err("not well-formed".to_string());
+                return Err(CalcError::MalformedExpression { index: last_index });
             }
-            return num_stack.pop().ok_or("not well-formed".to_string());
+            // E.g. an overflow or a variable without a value, which would end up on the tape
+            let result = num_stack.pop().unwrap();
+            result
+                .value
+                .to_result(CalcError::DomainError { index: last_index })?;
+            return Ok(result);
         }
-        let temp_token3: Token = rpn_notation
-            .pop_front()
-            .ok_or("popping empty rpn stack".to_string())?;
+        let (index, temp_token3) = rpn_notation.pop_front().unwrap();
+        last_index = index;
         match temp_token3 {
-            Token::Num(num) => num_stack.push(num),
+            Token::Num(num) => num_stack.push(Quantity::number(num)),
+            Token::Unit(unit) => {
+                let quantity = num_stack.pop().ok_or(CalcError::EmptyStack { index })?;
+                num_stack.push(
+                    quantity
+                        .in_unit(unit)
+                        .ok_or(CalcError::DimensionMismatch { index })?,
+                );
+            }
             Token::Func(f) => {
-                let arg = num_stack.pop().ok_or("not well-formed".to_string())?;
-                num_stack.push(f.apply(arg)?);
+                let mut args = vec![Quantity::number(0.); f.arity()];
+                for arg in args.iter_mut().rev() {
+                    *arg = num_stack.pop().ok_or(CalcError::EmptyStack { index })?;
+                }
+                num_stack.push(apply_func(f, &args, angle_mode, index)?);
             }
             Token::Operator(op) => {
-                let arg2 = num_stack.pop().ok_or("not well-formed".to_string())?;
-                let arg1 = num_stack.pop().ok_or("not well-formed".to_string())?;
-                num_stack.push(op.apply(arg1, arg2)?);
+                let arg2 = num_stack.pop().ok_or(CalcError::EmptyStack { index })?;
+                let arg1 = num_stack.pop().ok_or(CalcError::EmptyStack { index })?;
+                num_stack.push(apply_operator(op, arg1, arg2, index)?);
+            }
+            // The number before the plus or minus stays on the stack for the operator
+            Token::Percent => {
+                let percentage = num_stack.pop().ok_or(CalcError::EmptyStack { index })?;
+                let base = *num_stack.last().ok_or(CalcError::EmptyStack { index })?;
+                num_stack.push(with_unit(base.value * percentage.value / 100., base.unit));
             }
             _ => {
-                return Err("not well-formed".to_string());
+                return Err(CalcError::MalformedExpression { index });
             }
         }
     }
 }
 
-fn digits_to_float(entry_field: &mut VecDeque<Token>) -> Result<f64, String> {
-    //The function will read digits from two-sided queue and return the corresponding float with
-    //digits popped
+fn with_unit(value: f64, unit: Option<Unit>) -> Quantity {
+    return match unit {
+        Some(unit) => Quantity::new(value, unit),
+        None => Quantity::number(value),
+    };
+}
 
-    let mut result: f64 = 0.0;
-    let mut current_token: Token;
-    let mut past_decimal_point: bool = false;
-    let mut decimal_position: i32 = 0;
-    loop {
-        if entry_field.is_empty() {
-            return Ok(result);
+// Converts the second quantity into the unit of the first, a number takes the unit of the other.
+// The second operand of a sum or difference is converted as a difference, see
+// Quantity::difference_in_unit.
+fn in_same_unit(
+    arg1: Quantity,
+    arg2: Quantity,
+    as_difference: bool,
+    index: usize,
+) -> Result<(Quantity, Quantity), CalcError> {
+    return match (arg1.unit, arg2.unit) {
+        (Some(unit), _) => {
+            let converted = if as_difference {
+                arg2.difference_in_unit(unit)
+            } else {
+                arg2.in_unit(unit)
+            };
+            Ok((
+                arg1,
+                converted.ok_or(CalcError::DimensionMismatch { index })?,
+            ))
+        }
+        (None, Some(unit)) => Ok((Quantity::new(arg1.value, unit), arg2)),
+        (None, None) => Ok((arg1, arg2)),
+    };
+}
+
+// Sums keep the unit, a product or quotient keeps it if only one of the operands has a unit.
+// Speeds, lengths and times combine, e.g. 120 nm / 100 kt is a time, a plain number times a time
+// is a plain number, like a fuel flow times a time or a stored product, and the quotient of two
+// quantities of the same dimension is a plain number. Other results, e.g. an area or a power of a
+// length, have no unit here and are errors rather than plain numbers.
+fn apply_operator(
+    op: Operator,
+    arg1: Quantity,
+    arg2: Quantity,
+    index: usize,
+) -> Result<Quantity, CalcError> {
+    return match op {
+        Operator::Plus | Operator::Minus => {
+            let (arg1, arg2) = in_same_unit(arg1, arg2, true, index)?;
+            Ok(with_unit(
+                op.apply(arg1.value, arg2.value, index)?,
+                arg1.unit,
+            ))
+        }
+        Operator::Multiply
+            if arg1.dimension == Dimension::Speed && arg2.unit == Some(Unit::Hour) =>
+        {
+            Ok(travelled(arg1, arg2.value))
+        }
+        Operator::Multiply
+            if arg1.unit == Some(Unit::Hour) && arg2.dimension == Dimension::Speed =>
+        {
+            Ok(travelled(arg2, arg1.value))
+        }
+        Operator::Multiply => {
+            let value = op.apply(arg1.value, arg2.value, index)?;
+            match (arg1.unit, arg2.unit) {
+                (Some(Unit::Hour), None) | (None, Some(Unit::Hour)) => Ok(Quantity::number(value)),
+                (Some(unit), None) | (None, Some(unit)) => Ok(Quantity::new(value, unit)),
+                (None, None) => Ok(Quantity::number(value)),
+                (Some(_), Some(_)) => Err(CalcError::DimensionMismatch { index }),
+            }
+        }
+        Operator::Divide => match (arg1.unit, arg2.unit) {
+            (Some(length), Some(Unit::Hour)) if arg1.dimension == Dimension::Length => {
+                // The speed in the unit that goes with the length, e.g. knots for nautical miles
+                let knots = op.apply(
+                    arg1.value.convert(length, Unit::NauticalMile),
+                    arg2.value,
+                    index,
+                )?;
+                let speed = length.speed_of_length().unwrap();
+                Ok(Quantity::new(knots.convert(Unit::Knot, speed), speed))
+            }
+            (Some(length), Some(speed))
+                if arg1.dimension == Dimension::Length && arg2.dimension == Dimension::Speed =>
+            {
+                let hours = op.apply(
+                    arg1.value.convert(length, Unit::NauticalMile),
+                    arg2.value.convert(speed, Unit::Knot),
+                    index,
+                )?;
+                Ok(Quantity::new(hours, Unit::Hour))
+            }
+            (Some(unit), None) => Ok(Quantity::new(
+                op.apply(arg1.value, arg2.value, index)?,
+                unit,
+            )),
+            (Some(_), Some(_)) if arg1.dimension == arg2.dimension => {
+                let (arg1, arg2) = in_same_unit(arg1, arg2, false, index)?;
+                Ok(Quantity::number(op.apply(arg1.value, arg2.value, index)?))
+            }
+            (None, None) => Ok(Quantity::number(op.apply(arg1.value, arg2.value, index)?)),
+            _ => Err(CalcError::DimensionMismatch { index }),
+        },
+        Operator::Power => match (arg1.unit, arg2.unit) {
+            (None, None) => Ok(Quantity::number(op.apply(arg1.value, arg2.value, index)?)),
+            _ => Err(CalcError::DimensionMismatch { index }),
+        },
+    };
+}
+
+// The length covered at the speed in the hours, in the length unit that goes with the speed
+fn travelled(speed: Quantity, hours: f64) -> Quantity {
+    let speed_unit = speed.unit.unwrap();
+    let length = speed_unit.length_of_speed().unwrap();
+    let nautical_miles = speed.value.convert(speed_unit, Unit::Knot) * hours;
+    return Quantity::new(nautical_miles.convert(Unit::NauticalMile, length), length);
+}
+
+// Only functions whose result has the unit of the arguments keep it. atan2 takes two quantities of
+// the same dimension, the other functions only take plain numbers, e.g. sin 30 nm is an error.
+fn apply_func(
+    f: Func,
+    args: &[Quantity],
+    angle_mode: AngleMode,
+    index: usize,
+) -> Result<Quantity, CalcError> {
+    return match f {
+        Func::Negation | Func::Abs => Ok(with_unit(
+            f.apply(&[args[0].value], angle_mode, index)?,
+            args[0].unit,
+        )),
+        Func::Min | Func::Max | Func::Hypot => {
+            let (arg1, arg2) = in_same_unit(args[0], args[1], false, index)?;
+            Ok(with_unit(
+                f.apply(&[arg1.value, arg2.value], angle_mode, index)?,
+                arg1.unit,
+            ))
+        }
+        Func::Atan2 => {
+            let (arg1, arg2) = in_same_unit(args[0], args[1], false, index)?;
+            Ok(Quantity::number(f.apply(
+                &[arg1.value, arg2.value],
+                angle_mode,
+                index,
+            )?))
         }
-        current_token = entry_field.pop_front().unwrap();
-        match current_token {
+        _ if args.iter().any(|arg| arg.unit.is_some()) => {
+            Err(CalcError::DimensionMismatch { index })
+        }
+        _ => {
+            let values: Vec<f64> = args.iter().map(|arg| arg.value).collect();
+            Ok(Quantity::number(f.apply(&values, angle_mode, index)?))
+        }
+    };
+}
+
+fn digits_to_float(entry_field: &mut VecDeque<IndexedToken>) -> Result<f64, CalcError> {
+    //The function will read a number like 1.225E-3 from two-sided queue and return the
+    //corresponding float with its tokens popped. The standard library converts the text, so that
+    //the float is the closest one to the typed number. A time like 1:35 or 0:12:30 is returned in
+    //hours and an angle like 47°27.5' in degrees.
+
+    let start = entry_field.front().map(|token| token.0).unwrap_or(0);
+    let mut text = String::from("0");
+    let mut past_decimal_point: bool = false;
+    let mut exponent_index: Option<usize> = None;
+    let mut colon_indices: Vec<usize> = Vec::new();
+    // Position of the unit, see Digit::angle_mark_position, and index of every angle mark
+    let mut angle_marks: Vec<(i32, usize)> = Vec::new();
+    // Only the last unit of an angle can have a fraction
+    let mut fraction_closed = false;
+    while let Some((index, token)) = entry_field.front().cloned() {
+        match token {
             Token::Digit(Digit::Period) => {
-                if past_decimal_point {
-                    return Err("digits can't be parsed as a number".to_string());
-                } else {
-                    past_decimal_point = true;
-                    decimal_position += 1;
+                if past_decimal_point || exponent_index.is_some() {
+                    return Err(CalcError::MalformedNumber { index });
                 }
+                past_decimal_point = true;
+                text.push('.');
             }
-            Token::Digit(digit) => {
-                if !past_decimal_point {
-                    result = result * 10. + digit.to_int().unwrap() as f64;
-                } else {
-                    result = result
-                        + (10.0 as f64).powf(-decimal_position as f64)
-                            * digit.to_int().unwrap() as f64;
-                    decimal_position += 1;
+            Token::Digit(Digit::Colon) => {
+                if past_decimal_point
+                    || exponent_index.is_some()
+                    || colon_indices.len() == 2
+                    || !angle_marks.is_empty()
+                {
+                    return Err(CalcError::MalformedNumber { index });
                 }
+                colon_indices.push(index);
+                text.push(':');
             }
-            _ => {
-                entry_field.push_front(current_token);
-                return Ok(result);
+            Token::Digit(Digit::Exponent) => {
+                if exponent_index.is_some() || !colon_indices.is_empty() || !angle_marks.is_empty()
+                {
+                    return Err(CalcError::MalformedNumber { index });
+                }
+                exponent_index = Some(index);
+                text.push('e');
+            }
+            Token::Digit(mark) if mark.angle_mark_position().is_some() => {
+                let position = mark.angle_mark_position().unwrap();
+                if exponent_index.is_some()
+                    || !colon_indices.is_empty()
+                    || fraction_closed
+                    || text.ends_with('|')
+                    || angle_marks
+                        .last()
+                        .is_some_and(|(last, _)| *last >= position)
+                {
+                    return Err(CalcError::MalformedNumber { index });
+                }
+                fraction_closed = past_decimal_point;
+                angle_marks.push((position, index));
+                text.push('|');
+            }
+            // Sign of the exponent
+            Token::Func(Func::Negation) if text.ends_with('e') => text.push('-'),
+            Token::Digit(_) if fraction_closed => {
+                return Err(CalcError::MalformedNumber { index });
             }
+            Token::Digit(digit) => text.push_str(&digit.show()),
+            _ => break,
         }
+        entry_field.pop_front();
+    }
+    if let Some(index) = exponent_index {
+        if text.ends_with('e') || text.ends_with('-') {
+            return Err(CalcError::MalformedNumber { index });
+        }
+    }
+
+    if !colon_indices.is_empty() {
+        // Hours, minutes and seconds, the minutes and seconds may exceed 59
+        let mut total = 0.;
+        let colon_indices_count = colon_indices.len() as i32;
+        let indices = std::iter::once(start).chain(colon_indices);
+        for (part, index) in text.split(':').zip(indices) {
+            if part.is_empty() {
+                return Err(CalcError::MalformedNumber { index });
+            }
+            let part: f64 = part
+                .parse()
+                .map_err(|_| CalcError::MalformedNumber { index })?;
+            total = total * 60. + part;
+        }
+        return Ok(total / 60_f64.powi(colon_indices_count));
+    }
+
+    if let Some((last_position, last_index)) = angle_marks.last().cloned() {
+        // Digits after the last mark are in the next smaller unit, as in 122°18'32
+        let mut positions: Vec<(i32, usize)> = angle_marks;
+        positions.push((last_position + 1, last_index));
+        let mut degrees = 0.;
+        for (part, (position, index)) in text.split('|').zip(positions) {
+            if part.is_empty() {
+                continue;
+            }
+            if position > 2 {
+                return Err(CalcError::MalformedNumber { index });
+            }
+            let part: f64 = part
+                .parse()
+                .map_err(|_| CalcError::MalformedNumber { index })?;
+            degrees += part / 60_f64.powi(position);
+        }
+        return Ok(degrees);
+    }
+
+    return text
+        .parse::<f64>()
+        .map_err(|_| CalcError::MalformedNumber { index: start })?
+        .to_result(CalcError::MalformedNumber { index: start });
+}
+
+#[cfg(test)]
+mod test_computation {
+    use super::*;
+    use crate::consts::*;
+
+    fn compute(keys: &[u16]) -> Result<f64, CalcError> {
+        let mut state = State::default();
+        for key in keys {
+            state
+                .entry_field
+                .push_back(button_number_to_token(*key).unwrap());
+        }
+        return compute_entry_field(&mut state);
+    }
+
+    fn evaluate(text: &str) -> Result<f64, CalcError> {
+        let mut state = State::default();
+        *state.entry_field = parse_expression(text).unwrap();
+        return compute_entry_field(&mut state);
+    }
+
+    #[test]
+    fn test_precedence() {
+        let cases = [
+            ("10-2+3", 11.),
+            ("10+2-3", 9.),
+            ("10-2-3", 5.),
+            ("8/2*4", 16.),
+            ("8*2/4", 4.),
+            ("8/2/2", 2.),
+            ("1-2*3+4", -1.),
+            ("2*3-4/2", 4.),
+            ("20/4-3*2+1", 0.),
+            ("2+3*4^2/8-1", 7.),
+            ("-3-4", -7.),
+            ("-3*-4", 12.),
+            ("10-(2+3)", 5.),
+            ("100/(2*5)/2", 5.),
+            ("7-2^2*1+1", 4.),
+            ("2*(3+4)*5", 70.),
+        ];
+        for (text, result) in cases.iter() {
+            assert_eq!(evaluate(text), Ok(*result), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_functions_with_two_arguments() {
+        assert_eq!(evaluate("max(1, 2)"), Ok(2.));
+        assert_eq!(evaluate("min(3-5, -1)*2"), Ok(-4.));
+        assert_eq!(evaluate("hypot(3, 4)+1"), Ok(6.));
+        assert_eq!(evaluate("atan2(1, 1)"), Ok(45.));
+        assert_eq!(evaluate("atan2(-1, 0)"), Ok(-90.));
+        assert_eq!(evaluate("max(1, min(5, 2^2))"), Ok(4.));
+        assert_eq!(evaluate("-max(1, 2)"), Ok(-2.));
+        assert_eq!(evaluate("max(1)"), Err(CalcError::EmptyStack { index: 0 }));
+        assert_eq!(
+            evaluate("max(1, 2, 3)"),
+            Err(CalcError::MalformedExpression { index: 0 })
+        );
+        assert_eq!(
+            evaluate("1, 2"),
+            Err(CalcError::MalformedExpression { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_roots_and_logarithms() {
+        assert_eq!(evaluate("sqrt 16"), Ok(4.));
+        assert_eq!(evaluate("2sqrt(3^2+4^2)"), Ok(10.));
+        assert_eq!(evaluate("cbrt -27"), Ok(-3.));
+        assert_eq!(evaluate("log10 1000"), Ok(3.));
+        assert_eq!(evaluate("ln exp 2"), Ok(2.));
+        assert_eq!(evaluate("abs(3-5)"), Ok(2.));
+        assert_eq!(
+            evaluate("sqrt -4"),
+            Err(CalcError::DomainError { index: 0 })
+        );
+        assert_eq!(evaluate("1+ln 0"), Err(CalcError::DomainError { index: 2 }));
+        assert_eq!(
+            evaluate("log10 -1"),
+            Err(CalcError::DomainError { index: 0 })
+        );
+        assert_eq!(
+            evaluate("exp 1000"),
+            Err(CalcError::DomainError { index: 0 })
+        );
+        assert_eq!(evaluate("0^-1"), Err(CalcError::DomainError { index: 1 }));
+    }
+
+    #[test]
+    fn test_angle_modes() {
+        let evaluate_in = |mode: AngleMode, text: &str| {
+            let mut state = State {
+                angle_mode: mode,
+                ..State::default()
+            };
+            *state.entry_field = parse_expression(text).unwrap();
+            return compute_entry_field(&mut state).unwrap();
+        };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12;
+        assert!(close(evaluate_in(AngleMode::Degrees, "sin 30"), 0.5));
+        assert!(close(evaluate_in(AngleMode::Radians, "cos 0"), 1.));
+        assert!(close(
+            evaluate_in(AngleMode::Radians, "sin(3.14159265358979/6)"),
+            0.5
+        ));
+        assert!(close(evaluate_in(AngleMode::Degrees, "atan2(1,1)"), 45.));
+        assert!(close(
+            evaluate_in(AngleMode::Radians, "4atan2(1,1)"),
+            std::f64::consts::PI
+        ));
+        assert!(close(
+            evaluate_in(AngleMode::Radians, "acos -1"),
+            std::f64::consts::PI
+        ));
+    }
+
+    #[test]
+    fn test_scientific_notation() {
+        assert_eq!(evaluate("1.225E-3"), Ok(0.001225));
+        assert_eq!(evaluate("6.02E23"), Ok(6.02e23));
+        assert_eq!(evaluate("0.1+2E1"), Ok(20.1));
+        assert_eq!(evaluate("-2E-2^2"), Ok(-0.0004));
+        assert_eq!(
+            compute(&[
+                ONE_BUTTON,
+                EXPONENT_BUTTON,
+                TWO_BUTTON,
+                EXPONENT_BUTTON,
+                TWO_BUTTON
+            ]),
+            Err(CalcError::MalformedNumber { index: 3 })
+        );
+        assert_eq!(
+            compute(&[ONE_BUTTON, EXPONENT_BUTTON, PLUS_BUTTON, TWO_BUTTON]),
+            Err(CalcError::MalformedNumber { index: 1 })
+        );
+        assert_eq!(
+            evaluate("1E400"),
+            Err(CalcError::MalformedNumber { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(evaluate("200+10%"), Ok(220.));
+        assert_eq!(evaluate("200-10%"), Ok(180.));
+        assert_eq!(evaluate("50*20%"), Ok(10.));
+        assert_eq!(evaluate("50/20%"), Ok(250.));
+        assert_eq!(evaluate("25%"), Ok(0.25));
+        assert_eq!(evaluate("2*600+15%"), Ok(1380.));
+        assert_eq!(evaluate("100+(5+5)%"), Ok(110.));
+        assert_eq!(evaluate("10%2"), Ok(0.2));
+        assert!(parse_expression("10+%").is_err());
+        assert!(parse_expression("10%%").is_err());
+    }
+
+    #[test]
+    fn test_times() {
+        assert_eq!(evaluate("1:30"), Ok(1.5));
+        assert_eq!(evaluate("0:12:36"), Ok(0.21));
+        assert_eq!(evaluate("2:00+0:45-0:15"), Ok(2.5));
+        // Fuel flow in gallons per hour times a flight time
+        assert_eq!(evaluate("8.4*1:15"), Ok(10.5));
+        assert_eq!(evaluate("0:00:30.5"), Ok(30.5 / 3600.));
+        assert_eq!(
+            compute(&[ONE_BUTTON, COLON_BUTTON, PLUS_BUTTON, TWO_BUTTON]),
+            Err(CalcError::MalformedNumber { index: 1 })
+        );
+        assert!(parse_expression("1:2:3:4").is_err());
+        assert!(parse_expression("1.5:30").is_err());
+        assert!(parse_expression("1:30E2").is_err());
+        assert_eq!(format_time(2.5), "2:30:00");
+        assert_eq!(format_time(10.5 / 60.), "0:10:30");
+        assert_eq!(format_time(-1.25), "-1:15:00");
+        assert_eq!(format_time(-0.0001), "0:00:00");
+        assert_eq!(DisplayMode::Time.format(1. / 3.), "0:20:00");
+    }
+
+    #[test]
+    fn test_coordinates() {
+        assert_eq!(evaluate("N47°27.5'"), Ok(47. + 27.5 / 60.));
+        assert_eq!(
+            evaluate("W122 18 32"),
+            Ok(-(122. + 18. / 60. + 32. / 3600.))
+        );
+        assert_eq!(evaluate("S33°52'"), Ok(-(33. + 52. / 60.)));
+        assert_eq!(evaluate("2*10°30'"), Ok(21.));
+        assert_eq!(evaluate("S91"), Err(CalcError::DomainError { index: 0 }));
+        assert_eq!(
+            compute(&[
+                ONE_BUTTON,
+                DEGREE_BUTTON,
+                TWO_BUTTON,
+                PERIOD_BUTTON,
+                FIVE_BUTTON,
+                MINUTE_BUTTON,
+                THREE_BUTTON
+            ]),
+            Err(CalcError::MalformedNumber { index: 6 })
+        );
+        assert_eq!(
+            compute(&[NORTH_BUTTON, PLUS_BUTTON, ONE_BUTTON]),
+            Err(CalcError::MalformedNumber { index: 0 })
+        );
+        assert!(parse_expression("47'30°").is_err());
+        assert!(parse_expression("N+1").is_err());
+        assert_eq!(format_latitude(47. + 27.5 / 60.), "N47°27'30.0\"");
+        assert_eq!(format_longitude(-122.3089), "W122°18'32.0\"");
+        assert_eq!(format_dms(-0.5), "-0°30'00.0\"");
+    }
+
+    #[test]
+    fn test_power() {
+        assert_eq!(evaluate("2^3"), Ok(8.));
+        assert_eq!(evaluate("2^3^2"), Ok(512.));
+        assert_eq!(evaluate("-2^2"), Ok(-4.));
+        assert_eq!(evaluate("(-2)^2"), Ok(4.));
+        assert_eq!(evaluate("2^-1"), Ok(0.5));
+        assert_eq!(evaluate("3*2^2+1"), Ok(13.));
+        assert_eq!(evaluate("2^2*3"), Ok(12.));
+        assert_eq!(evaluate("16^0.5/2"), Ok(2.));
+        assert_eq!(evaluate("2^(1+1)^2"), Ok(16.));
+        assert_eq!(
+            evaluate("(-8)^0.5"),
+            Err(CalcError::DomainError { index: 4 })
+        );
+        assert_eq!(compute(&[THREE_BUTTON, POWER_BUTTON, TWO_BUTTON]), Ok(9.));
+    }
+
+    #[test]
+    fn test_units() {
+        let feet_in_nm = 1000_f64.convert(Unit::Foot, Unit::NauticalMile);
+        assert_eq!(evaluate("5 nm + 1000 ft"), Ok(5. + feet_in_nm));
+        assert_eq!(evaluate("(5 nm + 1000 ft) nm"), Ok(5. + feet_in_nm));
+        assert_eq!(
+            evaluate("5nm ft"),
+            Ok(5_f64.convert(Unit::NauticalMile, Unit::Foot))
+        );
+        assert_eq!(evaluate("2 + 3 kt"), Ok(5.));
+        assert_eq!(
+            evaluate("max(1 nm, 1 km) km"),
+            Ok(1_f64.convert(Unit::NauticalMile, Unit::Kilometer))
+        );
+        assert_eq!(evaluate("120 nm / 2 nm"), Ok(60.));
+        assert_eq!(
+            evaluate("(-2 gal * 3) l"),
+            Ok(-6_f64.convert(Unit::Gallon, Unit::Liter))
+        );
+        assert_eq!(
+            evaluate("1 kt + 1 gal"),
+            Err(CalcError::DimensionMismatch { index: 2 })
+        );
+        assert_eq!(
+            evaluate("1 kt ft"),
+            Err(CalcError::DimensionMismatch { index: 2 })
+        );
+        assert_eq!(
+            compute(&[
+                ONE_BUTTON,
+                KNOT_UNIT_BUTTON,
+                PLUS_BUTTON,
+                ONE_BUTTON,
+                GALLON_UNIT_BUTTON
+            ]),
+            Err(CalcError::DimensionMismatch { index: 2 })
+        );
+    }
+
+    #[test]
+    fn test_results_without_a_unit() {
+        assert_eq!(
+            evaluate("1 nm * 1 nm + 5 gal"),
+            Err(CalcError::DimensionMismatch { index: 2 })
+        );
+        assert_eq!(
+            evaluate("2 nm ^ 2 + 1 kt"),
+            Err(CalcError::DimensionMismatch { index: 2 })
+        );
+        assert_eq!(
+            evaluate("2 ^ 1 nm"),
+            Err(CalcError::DimensionMismatch { index: 1 })
+        );
+        assert_eq!(
+            evaluate("5 gal / 1 nm"),
+            Err(CalcError::DimensionMismatch { index: 2 })
+        );
+        assert_eq!(
+            evaluate("1 / 2 kt"),
+            Err(CalcError::DimensionMismatch { index: 1 })
+        );
+        assert_eq!(
+            evaluate("sin(30 nm)"),
+            Err(CalcError::DimensionMismatch { index: 0 })
+        );
+        assert_eq!(
+            evaluate("sqrt 4 gal"),
+            Err(CalcError::DimensionMismatch { index: 0 })
+        );
+        assert_eq!(
+            evaluate("ln(2 kt)"),
+            Err(CalcError::DimensionMismatch { index: 0 })
+        );
+        assert!((evaluate("atan2(1 nm, 1852 m)").unwrap() - 45.).abs() < 1e-9);
+        assert_eq!(evaluate("2 ^ 3 + 1 kt"), Ok(9.));
+    }
+
+    #[test]
+    fn test_temperature_differences() {
+        let mut state = State::default();
+        *state.entry_field = parse_expression("tmp + 18 degF").unwrap();
+        assert!((compute_entry_field(&mut state).unwrap() - 25.).abs() < 1e-9);
+        assert!((evaluate("20 degC - 9 degF").unwrap() - 15.).abs() < 1e-9);
+        assert_eq!(evaluate("(10 degC + 5) degF"), Ok(59.));
+        assert_eq!(evaluate("max(0 degC, 50 degF)"), Ok(10.));
+    }
+
+    #[test]
+    fn test_speeds_lengths_and_times() {
+        assert_eq!(evaluate("120 kt * 1:30 + 10 nm"), Ok(190.));
+        assert_eq!(evaluate("(1:30 * 120 kt) nm"), Ok(180.));
+        assert_eq!(evaluate("(120 nm / 1:00) kt"), Ok(120.));
+        assert!((evaluate("10 km / 0:30").unwrap() - 20.).abs() < 1e-9);
+        assert_eq!(evaluate("(120 nm / 80 kt) h"), Ok(1.5));
+        assert!((evaluate("60 m / 1:00").unwrap() - 1.).abs() < 1e-9);
+        assert!((evaluate("(1 mpm) kph").unwrap() - 0.06).abs() < 1e-9);
+        assert!((evaluate("50 mpm * 2:00 + 1 m").unwrap() - 6001.).abs() < 1e-9);
+        assert_eq!(evaluate("1:30 + 15"), Ok(16.5));
+        // The same as a product stored in a variable, which has no unit
+        let mut state = State::default();
+        state.variable_values.set(Variable::A, 8.4 * 1.25);
+        *state.entry_field = parse_expression("a + 5 gal").unwrap();
+        let stored = compute_entry_field(&mut state);
+        assert_eq!(evaluate("8.4*1:15 + 5 gal"), stored);
+        assert_eq!(stored, Ok(15.5));
+        assert_eq!(evaluate("(8.4*1:15) l"), Ok(10.5));
+        assert_eq!(evaluate("2*1:30 + 0:30"), Ok(3.5));
+        assert_eq!(
+            evaluate("1:30 + 1 nm"),
+            Err(CalcError::DimensionMismatch { index: 4 })
+        );
+    }
+
+    #[test]
+    fn test_errors_point_at_tokens() {
+        assert_eq!(
+            compute(&[
+                ONE_BUTTON,
+                DIVIDE_BUTTON,
+                LEFT_PARENTH_BUTTON,
+                ZERO_BUTTON,
+                RIGHT_PARENTH_BUTTON
+            ]),
+            Err(CalcError::DivisionByZero { index: 1 })
+        );
+        assert_eq!(
+            compute(&[TWO_BUTTON, RIGHT_PARENTH_BUTTON]),
+            Err(CalcError::MismatchedParenthesis { index: 1 })
+        );
+        assert_eq!(
+            compute(&[LEFT_PARENTH_BUTTON, TWO_BUTTON]),
+            Err(CalcError::MismatchedParenthesis { index: 0 })
+        );
+        assert_eq!(
+            compute(&[
+                THREE_BUTTON,
+                PLUS_BUTTON,
+                ONE_BUTTON,
+                PERIOD_BUTTON,
+                TWO_BUTTON,
+                PERIOD_BUTTON
+            ]),
+            Err(CalcError::MalformedNumber { index: 5 })
+        );
+        assert_eq!(
+            compute(&[ARCSIN_BUTTON, TWO_BUTTON]),
+            Err(CalcError::DomainError { index: 0 })
+        );
+        assert_eq!(
+            compute(&[ONE_BUTTON, PLUS_BUTTON]),
+            Err(CalcError::EmptyStack { index: 1 })
+        );
+        assert_eq!(compute(&[ONE_BUTTON, PLUS_BUTTON, TWO_BUTTON]), Ok(3.));
     }
 }
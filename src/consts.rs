@@ -1,14 +1,18 @@
 use crate::objects::*;
 
-//----------------String display function numbers: correspond to elements in the vector STRING_DISPLAY_FUNCTIONS
-pub const ENTRY_FIELD_WRITER: usize = 0;
-pub const PREVIOUS_ENTRY_FIELD_WRITER: usize = 1;
-
 // ----------------Button numbers-----------------
 // Separately handled buttons >=1000
 pub const EQUALS_BUTTON: u16 = 1000;
 pub const BACKSPACE_BUTTON: u16 = 1001;
 pub const CLEAR_BUTTON: u16 = 1002;
+pub const UNDO_BUTTON: u16 = 1003;
+pub const REDO_BUTTON: u16 = 1004;
+pub const CURSOR_LEFT_BUTTON: u16 = 1005;
+pub const CURSOR_RIGHT_BUTTON: u16 = 1006;
+pub const CURSOR_HOME_BUTTON: u16 = 1007;
+pub const CURSOR_END_BUTTON: u16 = 1008;
+pub const ANGLE_MODE_BUTTON: u16 = 1009;
+pub const TIME_DISPLAY_BUTTON: u16 = 1010;
 
 // Add symbol to entry field buttons 0-99
 pub const ZERO_BUTTON: u16 = 0;
@@ -27,6 +31,19 @@ pub const PLUS_BUTTON: u16 = 11;
 pub const MINUS_BUTTON: u16 = 12;
 pub const MULTIPLY_BUTTON: u16 = 13;
 pub const DIVIDE_BUTTON: u16 = 14;
+pub const POWER_BUTTON: u16 = 17;
+pub const COMMA_BUTTON: u16 = 18;
+pub const EXPONENT_BUTTON: u16 = 19;
+pub const PERCENT_BUTTON: u16 = 20;
+pub const COLON_BUTTON: u16 = 27;
+pub const DEGREE_BUTTON: u16 = 28;
+pub const MINUTE_BUTTON: u16 = 29;
+pub const SECOND_BUTTON: u16 = 37;
+
+pub const NORTH_BUTTON: u16 = 45;
+pub const SOUTH_BUTTON: u16 = 46;
+pub const EAST_BUTTON: u16 = 47;
+pub const WEST_BUTTON: u16 = 48;
 
 pub const LEFT_PARENTH_BUTTON: u16 = 15;
 pub const RIGHT_PARENTH_BUTTON: u16 = 16;
@@ -40,6 +57,18 @@ pub const ARCTAN_BUTTON: u16 = 26;
 
 pub const ANS_BUTTON: u16 = 30;
 
+pub const SQRT_BUTTON: u16 = 31;
+pub const CBRT_BUTTON: u16 = 32;
+pub const LN_BUTTON: u16 = 33;
+pub const LOG10_BUTTON: u16 = 34;
+pub const EXP_BUTTON: u16 = 35;
+pub const ABS_BUTTON: u16 = 36;
+
+pub const ATAN2_BUTTON: u16 = 71;
+pub const MIN_BUTTON: u16 = 72;
+pub const MAX_BUTTON: u16 = 73;
+pub const HYPOT_BUTTON: u16 = 74;
+
 pub const A_BUTTON: u16 = 41;
 pub const B_BUTTON: u16 = 42;
 pub const C_BUTTON: u16 = 43;
@@ -118,112 +147,235 @@ pub const HECTOPASCALS_TO_INHG_BUTTON: u16 = 322;
 pub const FEET_TO_METERS: u16 = 323;
 pub const METERS_TO_FEET: u16 = 324;
 
-pub fn button_number_to_token(button: u16) -> Token {
-    //This function should clean up the code quite a bit
-    match button {
-        ZERO_BUTTON => Token::Digit(Digit::Zero),
-        ONE_BUTTON => Token::Digit(Digit::One),
-        TWO_BUTTON => Token::Digit(Digit::Two),
-        THREE_BUTTON => Token::Digit(Digit::Three),
-        FOUR_BUTTON => Token::Digit(Digit::Four),
-        FIVE_BUTTON => Token::Digit(Digit::Five),
-        SIX_BUTTON => Token::Digit(Digit::Six),
-        SEVEN_BUTTON => Token::Digit(Digit::Seven),
-        EIGHT_BUTTON => Token::Digit(Digit::Eight),
-        NINE_BUTTON => Token::Digit(Digit::Nine),
-        PERIOD_BUTTON => Token::Digit(Digit::Period),
-        PLUS_BUTTON => Token::Operator(Operator::Plus),
-        MINUS_BUTTON => Token::Operator(Operator::Minus),
-        MULTIPLY_BUTTON => Token::Operator(Operator::Multiply),
-        DIVIDE_BUTTON => Token::Operator(Operator::Divide),
-        LEFT_PARENTH_BUTTON => Token::Parenth(Parenth::Left),
-        RIGHT_PARENTH_BUTTON => Token::Parenth(Parenth::Right),
-        SIN_BUTTON => Token::Func(Func::Sin),
-        COS_BUTTON => Token::Func(Func::Cos),
-        TAN_BUTTON => Token::Func(Func::Tan),
-        ARCSIN_BUTTON => Token::Func(Func::Arcsin),
-        ARCCOS_BUTTON => Token::Func(Func::Arccos),
-        ARCTAN_BUTTON => Token::Func(Func::Arctan),
-        ANS_BUTTON => Token::Variable(Variable::Ans),
-        A_BUTTON => Token::Variable(Variable::A),
-        B_BUTTON => Token::Variable(Variable::B),
-        C_BUTTON => Token::Variable(Variable::C),
-        D_BUTTON => Token::Variable(Variable::D),
-        TEMP_BUTTON => Token::Variable(Variable::Temp),
-        DEW_POINT_BUTTON => Token::Variable(Variable::DewPoint),
-        ALTITUDE_BUTTON => Token::Variable(Variable::Altitude),
-        ALTIMETER_BUTTON => Token::Variable(Variable::Altimeter),
-        WIND_SPEED_BUTTON => Token::Variable(Variable::WindSpeed),
-        WIND_HEADING_BUTTON => Token::Variable(Variable::WindDir),
-        COURSE_BUTTON => Token::Variable(Variable::Course),
-        CALIBRATED_AIRSPEED_BUTTON => Token::Variable(Variable::Cas),
-        PRESSURE_ALTITUDE_BUTTON => Token::Variable(Variable::PressAlt),
-        DENSITY_ALTITUDE_BUTTON => Token::Variable(Variable::DensAlt),
-        HEAD_WIND_BUTTON => Token::Variable(Variable::HeadWind),
-        CROSS_WIND_BUTTON => Token::Variable(Variable::CrossWind),
-        HEADING_BUTTON => Token::Variable(Variable::Heading),
-        TRUE_AIRSPEED_BUTTON => Token::Variable(Variable::Tas),
-        GROUND_SPEED_BUTTON => Token::Variable(Variable::GrdSpd),
-        A_ASSIGN_BUTTON => Token::Variable(Variable::A),
-        B_ASSIGN_BUTTON => Token::Variable(Variable::B),
-        C_ASSIGN_BUTTON => Token::Variable(Variable::C),
-        D_ASSIGN_BUTTON => Token::Variable(Variable::D),
-        TEMP_ASSIGN_BUTTON => Token::Variable(Variable::Temp),
-        DEW_POINT_ASSIGN_BUTTON => Token::Variable(Variable::DewPoint),
-        ALTITUDE_ASSIGN_BUTTON => Token::Variable(Variable::Altitude),
-        ALTIMETER_ASSIGN_BUTTON => Token::Variable(Variable::Altimeter),
-        WIND_SPEED_ASSIGN_BUTTON => Token::Variable(Variable::WindSpeed),
-        WIND_HEADING_ASSIGN_BUTTON => Token::Variable(Variable::WindDir),
-        COURSE_ASSIGN_BUTTON => Token::Variable(Variable::Course),
-        CALIBRATED_AIRSPEED_ASSIGN_BUTTON => Token::Variable(Variable::Cas),
-        A_ADD_BUTTON => Token::Variable(Variable::A),
-        B_ADD_BUTTON => Token::Variable(Variable::B),
-        C_ADD_BUTTON => Token::Variable(Variable::C),
-        D_ADD_BUTTON => Token::Variable(Variable::D),
-        TEMP_ADD_BUTTON => Token::Variable(Variable::Temp),
-        DEW_POINT_ADD_BUTTON => Token::Variable(Variable::DewPoint),
-        ALTITUDE_ADD_BUTTON => Token::Variable(Variable::Altitude),
-        ALTIMETER_ADD_BUTTON => Token::Variable(Variable::Altimeter),
-        WIND_SPEED_ADD_BUTTON => Token::Variable(Variable::WindSpeed),
-        WIND_HEADING_ADD_BUTTON => Token::Variable(Variable::WindDir),
-        COURSE_ADD_BUTTON => Token::Variable(Variable::Course),
-        CALIBRATED_AIRSPEED_ADD_BUTTON => Token::Variable(Variable::Cas),
-
-        _ => {
-            panic!("This match should be exhaustive.")
-        }
-    }
+//Formula buttons 400-499
+pub const A_FORMULA_BUTTON: u16 = 401;
+pub const B_FORMULA_BUTTON: u16 = 402;
+pub const C_FORMULA_BUTTON: u16 = 403;
+pub const D_FORMULA_BUTTON: u16 = 404;
+
+//Unit buttons 500-599, a unit follows a number as in 5 nm
+pub const NAUTICAL_MILE_UNIT_BUTTON: u16 = 501;
+pub const MILE_UNIT_BUTTON: u16 = 502;
+pub const KILOMETER_UNIT_BUTTON: u16 = 503;
+pub const FOOT_UNIT_BUTTON: u16 = 504;
+pub const METER_UNIT_BUTTON: u16 = 505;
+pub const KNOT_UNIT_BUTTON: u16 = 506;
+pub const FEET_PER_MINUTE_UNIT_BUTTON: u16 = 507;
+pub const MILES_PER_HOUR_UNIT_BUTTON: u16 = 508;
+pub const KILOMETERS_PER_HOUR_UNIT_BUTTON: u16 = 509;
+pub const METERS_PER_MINUTE_UNIT_BUTTON: u16 = 510;
+pub const LITER_UNIT_BUTTON: u16 = 511;
+pub const GALLON_UNIT_BUTTON: u16 = 512;
+pub const KILOGRAM_UNIT_BUTTON: u16 = 513;
+pub const POUND_UNIT_BUTTON: u16 = 514;
+pub const CELCIUS_UNIT_BUTTON: u16 = 515;
+pub const KELVIN_UNIT_BUTTON: u16 = 516;
+pub const FAHRENHEIT_UNIT_BUTTON: u16 = 517;
+pub const AVIGAS_POUND_UNIT_BUTTON: u16 = 518;
+pub const JETFUEL_POUND_UNIT_BUTTON: u16 = 519;
+pub const INHG_UNIT_BUTTON: u16 = 520;
+pub const KILOPASCAL_UNIT_BUTTON: u16 = 521;
+pub const HECTOPASCAL_UNIT_BUTTON: u16 = 522;
+pub const PASCAL_UNIT_BUTTON: u16 = 523;
+pub const HOUR_UNIT_BUTTON: u16 = 524;
+
+// ----------------Button tables-----------------
+// Every button is listed exactly once, so a button number and the key it stands for can be
+// translated into each other without loss.
+pub const TOKEN_BUTTONS: [(u16, Token); 65] = [
+    (ZERO_BUTTON, Token::Digit(Digit::Zero)),
+    (ONE_BUTTON, Token::Digit(Digit::One)),
+    (TWO_BUTTON, Token::Digit(Digit::Two)),
+    (THREE_BUTTON, Token::Digit(Digit::Three)),
+    (FOUR_BUTTON, Token::Digit(Digit::Four)),
+    (FIVE_BUTTON, Token::Digit(Digit::Five)),
+    (SIX_BUTTON, Token::Digit(Digit::Six)),
+    (SEVEN_BUTTON, Token::Digit(Digit::Seven)),
+    (EIGHT_BUTTON, Token::Digit(Digit::Eight)),
+    (NINE_BUTTON, Token::Digit(Digit::Nine)),
+    (PERIOD_BUTTON, Token::Digit(Digit::Period)),
+    (EXPONENT_BUTTON, Token::Digit(Digit::Exponent)),
+    (COLON_BUTTON, Token::Digit(Digit::Colon)),
+    (DEGREE_BUTTON, Token::Digit(Digit::Degree)),
+    (MINUTE_BUTTON, Token::Digit(Digit::Minute)),
+    (SECOND_BUTTON, Token::Digit(Digit::Second)),
+    (NORTH_BUTTON, Token::Hemisphere(Hemisphere::North)),
+    (SOUTH_BUTTON, Token::Hemisphere(Hemisphere::South)),
+    (EAST_BUTTON, Token::Hemisphere(Hemisphere::East)),
+    (WEST_BUTTON, Token::Hemisphere(Hemisphere::West)),
+    (PLUS_BUTTON, Token::Operator(Operator::Plus)),
+    (MINUS_BUTTON, Token::Operator(Operator::Minus)),
+    (MULTIPLY_BUTTON, Token::Operator(Operator::Multiply)),
+    (DIVIDE_BUTTON, Token::Operator(Operator::Divide)),
+    (POWER_BUTTON, Token::Operator(Operator::Power)),
+    (LEFT_PARENTH_BUTTON, Token::Parenth(Parenth::Left)),
+    (RIGHT_PARENTH_BUTTON, Token::Parenth(Parenth::Right)),
+    (COMMA_BUTTON, Token::Comma),
+    (PERCENT_BUTTON, Token::Percent),
+    (SIN_BUTTON, Token::Func(Func::Sin)),
+    (COS_BUTTON, Token::Func(Func::Cos)),
+    (TAN_BUTTON, Token::Func(Func::Tan)),
+    (ARCSIN_BUTTON, Token::Func(Func::Arcsin)),
+    (ARCCOS_BUTTON, Token::Func(Func::Arccos)),
+    (ARCTAN_BUTTON, Token::Func(Func::Arctan)),
+    (ATAN2_BUTTON, Token::Func(Func::Atan2)),
+    (SQRT_BUTTON, Token::Func(Func::Sqrt)),
+    (CBRT_BUTTON, Token::Func(Func::Cbrt)),
+    (LN_BUTTON, Token::Func(Func::Ln)),
+    (LOG10_BUTTON, Token::Func(Func::Log10)),
+    (EXP_BUTTON, Token::Func(Func::Exp)),
+    (ABS_BUTTON, Token::Func(Func::Abs)),
+    (MIN_BUTTON, Token::Func(Func::Min)),
+    (MAX_BUTTON, Token::Func(Func::Max)),
+    (HYPOT_BUTTON, Token::Func(Func::Hypot)),
+    (ANS_BUTTON, Token::Variable(Variable::Ans)),
+    (A_BUTTON, Token::Variable(Variable::A)),
+    (B_BUTTON, Token::Variable(Variable::B)),
+    (C_BUTTON, Token::Variable(Variable::C)),
+    (D_BUTTON, Token::Variable(Variable::D)),
+    (TEMP_BUTTON, Token::Variable(Variable::Temp)),
+    (DEW_POINT_BUTTON, Token::Variable(Variable::DewPoint)),
+    (ALTITUDE_BUTTON, Token::Variable(Variable::Altitude)),
+    (ALTIMETER_BUTTON, Token::Variable(Variable::Altimeter)),
+    (WIND_SPEED_BUTTON, Token::Variable(Variable::WindSpeed)),
+    (WIND_HEADING_BUTTON, Token::Variable(Variable::WindDir)),
+    (COURSE_BUTTON, Token::Variable(Variable::Course)),
+    (CALIBRATED_AIRSPEED_BUTTON, Token::Variable(Variable::Cas)),
+    (
+        PRESSURE_ALTITUDE_BUTTON,
+        Token::Variable(Variable::PressAlt),
+    ),
+    (DENSITY_ALTITUDE_BUTTON, Token::Variable(Variable::DensAlt)),
+    (HEAD_WIND_BUTTON, Token::Variable(Variable::HeadWind)),
+    (CROSS_WIND_BUTTON, Token::Variable(Variable::CrossWind)),
+    (HEADING_BUTTON, Token::Variable(Variable::Heading)),
+    (TRUE_AIRSPEED_BUTTON, Token::Variable(Variable::Tas)),
+    (GROUND_SPEED_BUTTON, Token::Variable(Variable::GrdSpd)),
+];
+
+pub const ASSIGN_BUTTONS: [(u16, Variable); 12] = [
+    (A_ASSIGN_BUTTON, Variable::A),
+    (B_ASSIGN_BUTTON, Variable::B),
+    (C_ASSIGN_BUTTON, Variable::C),
+    (D_ASSIGN_BUTTON, Variable::D),
+    (TEMP_ASSIGN_BUTTON, Variable::Temp),
+    (DEW_POINT_ASSIGN_BUTTON, Variable::DewPoint),
+    (ALTITUDE_ASSIGN_BUTTON, Variable::Altitude),
+    (ALTIMETER_ASSIGN_BUTTON, Variable::Altimeter),
+    (WIND_SPEED_ASSIGN_BUTTON, Variable::WindSpeed),
+    (WIND_HEADING_ASSIGN_BUTTON, Variable::WindDir),
+    (COURSE_ASSIGN_BUTTON, Variable::Course),
+    (CALIBRATED_AIRSPEED_ASSIGN_BUTTON, Variable::Cas),
+];
+
+pub const ADD_BUTTONS: [(u16, Variable); 12] = [
+    (A_ADD_BUTTON, Variable::A),
+    (B_ADD_BUTTON, Variable::B),
+    (C_ADD_BUTTON, Variable::C),
+    (D_ADD_BUTTON, Variable::D),
+    (TEMP_ADD_BUTTON, Variable::Temp),
+    (DEW_POINT_ADD_BUTTON, Variable::DewPoint),
+    (ALTITUDE_ADD_BUTTON, Variable::Altitude),
+    (ALTIMETER_ADD_BUTTON, Variable::Altimeter),
+    (WIND_SPEED_ADD_BUTTON, Variable::WindSpeed),
+    (WIND_HEADING_ADD_BUTTON, Variable::WindDir),
+    (COURSE_ADD_BUTTON, Variable::Course),
+    (CALIBRATED_AIRSPEED_ADD_BUTTON, Variable::Cas),
+];
+
+pub const FORMULA_BUTTONS: [(u16, Variable); 4] = [
+    (A_FORMULA_BUTTON, Variable::A),
+    (B_FORMULA_BUTTON, Variable::B),
+    (C_FORMULA_BUTTON, Variable::C),
+    (D_FORMULA_BUTTON, Variable::D),
+];
+
+pub const CONVERSION_BUTTONS: [(u16, Unit, Unit); 24] = [
+    (NM_TO_FEET_BUTTON, Unit::NauticalMile, Unit::Foot),
+    (FEET_TO_NM_BUTTON, Unit::Foot, Unit::NauticalMile),
+    (KNOTS_TO_FPM_BUTTON, Unit::Knot, Unit::FeetPerMinute),
+    (FPM_TO_KNOTS_BUTTON, Unit::FeetPerMinute, Unit::Knot),
+    (
+        KPH_TO_MPM_BUTTON,
+        Unit::KilometersPerHour,
+        Unit::MetersPerMinute,
+    ),
+    (
+        MPM_TO_KPH_BUTTON,
+        Unit::MetersPerMinute,
+        Unit::KilometersPerHour,
+    ),
+    (NM_TO_MILES_BUTTON, Unit::NauticalMile, Unit::Mile),
+    (MILES_TO_NM_BUTTON, Unit::Mile, Unit::NauticalMile),
+    (MILES_TO_KILOMETERS_BUTTON, Unit::Mile, Unit::Kilometer),
+    (KILOMETERS_TO_MILES_BUTTON, Unit::Kilometer, Unit::Mile),
+    (KNOTS_TO_MPH_BUTTON, Unit::Knot, Unit::MilesPerHour),
+    (MPH_TO_KNOTS_BUTTON, Unit::MilesPerHour, Unit::Knot),
+    (C_TO_F_BUTTON, Unit::Celcius, Unit::Fahrenheit),
+    (F_TO_C_BUTTON, Unit::Fahrenheit, Unit::Celcius),
+    (LBS_TO_KGS_BUTTON, Unit::Pound, Unit::Kilogram),
+    (KGS_TO_LBS_BUTTON, Unit::Kilogram, Unit::Pound),
+    (LITERS_TO_GALLONS_BUTTON, Unit::Liter, Unit::Gallon),
+    (GALLONS_TO_LITTERS_BUTTON, Unit::Gallon, Unit::Liter),
+    (
+        GALLONS_TO_AVIGAS_LBS_BUTTON,
+        Unit::Gallon,
+        Unit::AvigasPound,
+    ),
+    (
+        GALLONS_TO_JET_FUEL_LBS_BUTTON,
+        Unit::Gallon,
+        Unit::JetfuelPound,
+    ),
+    (INHG_TO_HECTOPASCALS_BUTTON, Unit::InHg, Unit::Hectopascal),
+    (HECTOPASCALS_TO_INHG_BUTTON, Unit::Hectopascal, Unit::InHg),
+    (FEET_TO_METERS, Unit::Foot, Unit::Meter),
+    (METERS_TO_FEET, Unit::Meter, Unit::Foot),
+];
+
+pub const UNIT_BUTTONS: [(u16, Unit); 24] = [
+    (NAUTICAL_MILE_UNIT_BUTTON, Unit::NauticalMile),
+    (MILE_UNIT_BUTTON, Unit::Mile),
+    (KILOMETER_UNIT_BUTTON, Unit::Kilometer),
+    (FOOT_UNIT_BUTTON, Unit::Foot),
+    (METER_UNIT_BUTTON, Unit::Meter),
+    (KNOT_UNIT_BUTTON, Unit::Knot),
+    (FEET_PER_MINUTE_UNIT_BUTTON, Unit::FeetPerMinute),
+    (MILES_PER_HOUR_UNIT_BUTTON, Unit::MilesPerHour),
+    (KILOMETERS_PER_HOUR_UNIT_BUTTON, Unit::KilometersPerHour),
+    (METERS_PER_MINUTE_UNIT_BUTTON, Unit::MetersPerMinute),
+    (LITER_UNIT_BUTTON, Unit::Liter),
+    (GALLON_UNIT_BUTTON, Unit::Gallon),
+    (KILOGRAM_UNIT_BUTTON, Unit::Kilogram),
+    (POUND_UNIT_BUTTON, Unit::Pound),
+    (CELCIUS_UNIT_BUTTON, Unit::Celcius),
+    (KELVIN_UNIT_BUTTON, Unit::Kelvin),
+    (FAHRENHEIT_UNIT_BUTTON, Unit::Fahrenheit),
+    (AVIGAS_POUND_UNIT_BUTTON, Unit::AvigasPound),
+    (JETFUEL_POUND_UNIT_BUTTON, Unit::JetfuelPound),
+    (INHG_UNIT_BUTTON, Unit::InHg),
+    (KILOPASCAL_UNIT_BUTTON, Unit::Kilopascal),
+    (HECTOPASCAL_UNIT_BUTTON, Unit::Hectopascal),
+    (PASCAL_UNIT_BUTTON, Unit::Pascal),
+    (HOUR_UNIT_BUTTON, Unit::Hour),
+];
+
+pub fn button_number_to_token(button: u16) -> Option<Token> {
+    return TOKEN_BUTTONS
+        .iter()
+        .find(|(number, _)| *number == button)
+        .map(|(_, token)| *token)
+        .or_else(|| {
+            UNIT_BUTTONS
+                .iter()
+                .find(|(number, _)| *number == button)
+                .map(|(_, unit)| Token::Unit(*unit))
+        });
 }
 
-pub fn button_number_to_pair_of_units(button: u16) -> (Unit, Unit) {
-    match button {
-        NM_TO_FEET_BUTTON => (Unit::NauticalMile, Unit::Foot),
-        FEET_TO_NM_BUTTON => (Unit::Foot, Unit::NauticalMile),
-        KNOTS_TO_FPM_BUTTON => (Unit::Knot, Unit::FeetPerMinute),
-        FPM_TO_KNOTS_BUTTON => (Unit::FeetPerMinute, Unit::Knot),
-        KPH_TO_MPM_BUTTON => (Unit::KilometersPerHour, Unit::MetersPerMinute),
-        MPM_TO_KPH_BUTTON => (Unit::MetersPerMinute, Unit::KilometersPerHour),
-        NM_TO_MILES_BUTTON => (Unit::NauticalMile, Unit::Mile),
-        MILES_TO_NM_BUTTON => (Unit::Mile, Unit::NauticalMile),
-        MILES_TO_KILOMETERS_BUTTON => (Unit::Mile, Unit::Kilometer),
-        KILOMETERS_TO_MILES_BUTTON => (Unit::Kilometer, Unit::Mile),
-        KNOTS_TO_MPH_BUTTON => (Unit::Knot, Unit::MilesPerHour),
-        MPH_TO_KNOTS_BUTTON => (Unit::MilesPerHour, Unit::Knot),
-        C_TO_F_BUTTON => (Unit::Celcius, Unit::Fahrenheit),
-        F_TO_C_BUTTON => (Unit::Fahrenheit, Unit::Celcius),
-        LBS_TO_KGS_BUTTON => (Unit::Pound, Unit::Kilogram),
-        KGS_TO_LBS_BUTTON => (Unit::Kilogram, Unit::Pound),
-        LITERS_TO_GALLONS_BUTTON => (Unit::Liter, Unit::Gallon),
-        GALLONS_TO_LITTERS_BUTTON => (Unit::Gallon, Unit::Liter),
-        GALLONS_TO_AVIGAS_LBS_BUTTON => (Unit::Gallon, Unit::AvigasPound),
-        GALLONS_TO_JET_FUEL_LBS_BUTTON => (Unit::Gallon, Unit::JetfuelPound),
-        INHG_TO_HECTOPASCALS_BUTTON => (Unit::InHg, Unit::Hectopascal),
-        HECTOPASCALS_TO_INHG_BUTTON => (Unit::Hectopascal, Unit::InHg),
-        FEET_TO_METERS => (Unit::Foot, Unit::Meter),
-        METERS_TO_FEET => (Unit::Meter, Unit::Foot),
-        _ => {
-            panic!("This match should be exhaustive");
-            (Unit::Celcius, Unit::Celcius) //To please the almighty compiler
-        }
-    }
+pub fn button_number_to_pair_of_units(button: u16) -> Option<(Unit, Unit)> {
+    return CONVERSION_BUTTONS
+        .iter()
+        .find(|(number, _, _)| *number == button)
+        .map(|(_, from, to)| (*from, *to));
 }
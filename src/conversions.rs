@@ -1,34 +1,34 @@
 use super::*;
 use crate::computation::*;
-use crate::consts::*;
+use crate::display::*;
 use crate::objects::*;
 
-use std::cell::RefCell;
-
 pub fn converstion_pressed(
-    key: u16,
-    state: &mut Box<State>,
-    string_writers: &mut Vec<RefCell<Box<dyn FnMut(String) -> ()>>>,
+    from: Unit,
+    to: Unit,
+    state: &mut State,
+    sink: &mut dyn DisplaySink,
 ) -> () {
     let value_to_convert: f64;
     if !state.entry_field.is_empty() {
-        let result = compute_entry_field(state);
+        // A quantity with a unit is converted from that unit, it has to measure the same thing
+        let result = compute_entry_field_in_unit(Some(from), state);
         match result {
             Err(err) => {
-                string_writers[ENTRY_FIELD_WRITER].borrow_mut()(err);
+                sink.error(err);
                 return ();
             }
             Ok(answer) => {
                 state.entry_field.clear();
-                update_string_field(state, string_writers, ENTRY_FIELD_WRITER);
+                state.cursor = 0;
+                show_entry_field(state, sink);
                 value_to_convert = answer;
             }
         }
     } else {
         value_to_convert = state.variable_values.get(Variable::Ans);
     }
-    let units = button_number_to_pair_of_units(key);
     let original_answer = value_to_convert;
-    let converted_answer = original_answer.convert(units.0, units.1);
+    let converted_answer = original_answer.convert(from, to);
     state.variable_values.set(Variable::Ans, converted_answer);
 }
@@ -0,0 +1,182 @@
+use crate::error::*;
+use crate::objects::*;
+use crate::tape::*;
+
+use std::sync::mpsc::Sender;
+
+// Everything a calculator shows on screen goes through a display sink. All methods do nothing by
+// default, so a front end only implements the ones it has a place for.
+pub trait DisplaySink {
+    // Called with the new contents of the entry field
+    fn entry_changed(&mut self, _entry: &str) -> () {}
+    // Called after entry_changed and when the cursor moves. The position is the number of
+    // characters of the entry field before the cursor.
+    fn cursor_moved(&mut self, _position: usize) -> () {}
+    // Called with the new contents of the previous entry field
+    fn history_changed(&mut self, _previous_entry: &str) -> () {}
+    fn variable_changed(&mut self, _var: Variable, _value: f64) -> () {}
+    // Called when the entry field can't be evaluated. The entry field is left as it is.
+    fn error(&mut self, _err: CalcError) -> () {}
+    // Called when a calculation was added to the tape or the tape was cleared
+    fn tape_changed(&mut self, _tape: &Tape) -> () {}
+    fn angle_mode_changed(&mut self, _mode: AngleMode) -> () {}
+    // Results should be shown with DisplayMode::format from now on
+    fn display_mode_changed(&mut self, _mode: DisplayMode) -> () {}
+    // Called when a formula was assigned to the variable, None when it was removed
+    fn formula_changed(&mut self, _var: Variable, _formula: Option<&str>) -> () {}
+    // Called when a user-named variable was set, None when it was deleted
+    fn named_variable_changed(&mut self, _name: &str, _value: Option<f64>) -> () {}
+}
+
+// A display sink that shows nothing, e.g. for computations in the background
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoDisplay;
+
+impl DisplaySink for NoDisplay {}
+
+impl<T: DisplaySink + ?Sized> DisplaySink for Box<T> {
+    fn entry_changed(&mut self, entry: &str) -> () {
+        (**self).entry_changed(entry);
+    }
+    fn cursor_moved(&mut self, position: usize) -> () {
+        (**self).cursor_moved(position);
+    }
+    fn history_changed(&mut self, previous_entry: &str) -> () {
+        (**self).history_changed(previous_entry);
+    }
+    fn variable_changed(&mut self, var: Variable, value: f64) -> () {
+        (**self).variable_changed(var, value);
+    }
+    fn error(&mut self, err: CalcError) -> () {
+        (**self).error(err);
+    }
+    fn tape_changed(&mut self, tape: &Tape) -> () {
+        (**self).tape_changed(tape);
+    }
+    fn angle_mode_changed(&mut self, mode: AngleMode) -> () {
+        (**self).angle_mode_changed(mode);
+    }
+    fn display_mode_changed(&mut self, mode: DisplayMode) -> () {
+        (**self).display_mode_changed(mode);
+    }
+    fn formula_changed(&mut self, var: Variable, formula: Option<&str>) -> () {
+        (**self).formula_changed(var, formula);
+    }
+    fn named_variable_changed(&mut self, name: &str, value: Option<f64>) -> () {
+        (**self).named_variable_changed(name, value);
+    }
+}
+
+// A change of the calculator screen, for front ends that receive the updates on another thread
+#[derive(Debug, Clone, PartialEq)]
+pub enum DisplayEvent {
+    EntryChanged(String),
+    CursorMoved(usize),
+    HistoryChanged(String),
+    VariableChanged(Variable, f64),
+    Error(CalcError),
+    TapeChanged(Tape),
+    AngleModeChanged(AngleMode),
+    DisplayModeChanged(DisplayMode),
+    FormulaChanged(Variable, Option<String>),
+    NamedVariableChanged(String, Option<f64>),
+}
+
+// Sends every change of the screen over a channel. Changes are dropped once the receiving end is
+// gone.
+impl DisplaySink for Sender<DisplayEvent> {
+    fn entry_changed(&mut self, entry: &str) -> () {
+        let _ = self.send(DisplayEvent::EntryChanged(entry.to_string()));
+    }
+    fn cursor_moved(&mut self, position: usize) -> () {
+        let _ = self.send(DisplayEvent::CursorMoved(position));
+    }
+    fn history_changed(&mut self, previous_entry: &str) -> () {
+        let _ = self.send(DisplayEvent::HistoryChanged(previous_entry.to_string()));
+    }
+    fn variable_changed(&mut self, var: Variable, value: f64) -> () {
+        let _ = self.send(DisplayEvent::VariableChanged(var, value));
+    }
+    fn error(&mut self, err: CalcError) -> () {
+        let _ = self.send(DisplayEvent::Error(err));
+    }
+    fn tape_changed(&mut self, tape: &Tape) -> () {
+        let _ = self.send(DisplayEvent::TapeChanged(tape.clone()));
+    }
+    fn angle_mode_changed(&mut self, mode: AngleMode) -> () {
+        let _ = self.send(DisplayEvent::AngleModeChanged(mode));
+    }
+    fn display_mode_changed(&mut self, mode: DisplayMode) -> () {
+        let _ = self.send(DisplayEvent::DisplayModeChanged(mode));
+    }
+    fn formula_changed(&mut self, var: Variable, formula: Option<&str>) -> () {
+        let _ = self.send(DisplayEvent::FormulaChanged(
+            var,
+            formula.map(|formula| formula.to_string()),
+        ));
+    }
+    fn named_variable_changed(&mut self, name: &str, value: Option<f64>) -> () {
+        let _ = self.send(DisplayEvent::NamedVariableChanged(name.to_string(), value));
+    }
+}
+
+#[cfg(test)]
+mod test_display {
+    use super::*;
+    use crate::key::*;
+    use crate::Calculator;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_recorded_key_presses() {
+        let (sender, events) = channel::<DisplayEvent>();
+        let sink: Box<dyn DisplaySink> = Box::new(sender);
+        let mut calculator = Calculator::new(State::default(), sink);
+        events.try_iter().for_each(drop);
+
+        calculator.press(Key::Token(Token::Digit(Digit::Two)));
+        calculator.press(Key::Token(Token::Operator(Operator::Plus)));
+        calculator.press(Key::Token(Token::Digit(Digit::Three)));
+        let recorded: Vec<DisplayEvent> = events.try_iter().collect();
+        assert_eq!(
+            recorded,
+            vec![
+                DisplayEvent::EntryChanged("2".to_string()),
+                DisplayEvent::CursorMoved(1),
+                DisplayEvent::EntryChanged("2+".to_string()),
+                DisplayEvent::CursorMoved(2),
+                DisplayEvent::EntryChanged("2+3".to_string()),
+                DisplayEvent::CursorMoved(3),
+            ]
+        );
+
+        // The previous answer is NaN and the tape has a timestamp, so only their order is checked
+        calculator.press(Key::Equals);
+        let recorded: Vec<DisplayEvent> = events.try_iter().collect();
+        assert_eq!(recorded.len(), 6);
+        assert_eq!(recorded[0], DisplayEvent::EntryChanged("".to_string()));
+        assert_eq!(recorded[1], DisplayEvent::CursorMoved(0));
+        assert_eq!(recorded[2], DisplayEvent::HistoryChanged("2+3".to_string()));
+        assert!(matches!(recorded[3], DisplayEvent::TapeChanged(_)));
+        assert!(matches!(
+            recorded[4],
+            DisplayEvent::VariableChanged(Variable::PrevAns, _)
+        ));
+        assert_eq!(
+            recorded[5],
+            DisplayEvent::VariableChanged(Variable::Ans, 5.)
+        );
+
+        calculator.press(Key::Token(Token::Parenth(Parenth::Left)));
+        calculator.press(Key::Equals);
+        let recorded: Vec<DisplayEvent> = events.try_iter().collect();
+        assert_eq!(
+            recorded,
+            vec![
+                DisplayEvent::EntryChanged("(".to_string()),
+                DisplayEvent::CursorMoved(1),
+                DisplayEvent::Error(CalcError::MismatchedParenthesis { index: 0 }),
+            ]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use crate::objects::*;
+use crate::tape::*;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use strum::IntoEnumIterator;
+
+// Version of the documents written by this crate. Bump it when a change of StateDocument can't be
+// expressed with a #[serde(default)] field, and convert older documents in State::from_document.
+pub const STATE_DOCUMENT_VERSION: u32 = 1;
+
+// Everything needed to restore a calculator, e.g. after the app was killed or to share a scenario.
+// Numbers that aren't finite are written as the strings "NaN", "inf" and "-inf", as JSON has no
+// such numbers. Variables left out of a document, e.g. a hand-written scenario, get the values of
+// State::default and the computed ones are computed from the others.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateDocument {
+    pub version: u32,
+    #[serde(default)]
+    pub entry_field: Vec<Token>,
+    #[serde(default)]
+    pub previous_entry_field: Vec<Token>,
+    #[serde(default, with = "float_map_format")]
+    pub variables: BTreeMap<Variable, f64>,
+    #[serde(default)]
+    pub tape: Tape,
+    // Documents without a cursor have it at the end of the entry field
+    #[serde(default)]
+    pub cursor: Option<usize>,
+    #[serde(default)]
+    pub angle_mode: AngleMode,
+    #[serde(default)]
+    pub display_mode: DisplayMode,
+    #[serde(default)]
+    pub formulas: BTreeMap<Variable, Vec<Token>>,
+    #[serde(default, with = "float_map_format")]
+    pub named_variables: BTreeMap<Name, f64>,
+}
+
+// A number of a document, see StateDocument
+struct DocumentFloat(f64);
+
+impl Serialize for DocumentFloat {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0.is_finite() {
+            return serializer.serialize_f64(self.0);
+        }
+        return serializer.serialize_str(&self.0.to_string());
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NumberOrText {
+    Number(f64),
+    Text(String),
+}
+
+impl<'de> Deserialize<'de> for DocumentFloat {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<DocumentFloat, D::Error> {
+        return match NumberOrText::deserialize(deserializer)? {
+            NumberOrText::Number(x) => Ok(DocumentFloat(x)),
+            NumberOrText::Text(text) => text
+                .parse()
+                .map(DocumentFloat)
+                .map_err(|_| D::Error::custom(format!("invalid number {:?}", text))),
+        };
+    }
+}
+
+// For #[serde(with)] on f64 fields that are part of a document
+pub(crate) mod float_format {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(x: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        return DocumentFloat(*x).serialize(serializer);
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        return DocumentFloat::deserialize(deserializer).map(|x| x.0);
+    }
+}
+
+mod float_map_format {
+    use super::*;
+
+    pub fn serialize<K: Serialize, S: Serializer>(
+        map: &BTreeMap<K, f64>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        return serializer.collect_map(map.iter().map(|(key, x)| (key, DocumentFloat(*x))));
+    }
+
+    pub fn deserialize<'de, K: Deserialize<'de> + Ord, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BTreeMap<K, f64>, D::Error> {
+        let map = BTreeMap::<K, DocumentFloat>::deserialize(deserializer)?;
+        return Ok(map.into_iter().map(|(key, x)| (key, x.0)).collect());
+    }
+}
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Json(serde_json::Error),
+    TomlRead(toml::de::Error),
+    TomlWrite(toml::ser::Error),
+    // The document was written by a newer version of the calculator
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentError::Json(err) => write!(f, "invalid JSON document: {}", err),
+            DocumentError::TomlRead(err) => write!(f, "invalid TOML document: {}", err),
+            DocumentError::TomlWrite(err) => write!(f, "can't write TOML document: {}", err),
+            DocumentError::UnsupportedVersion(version) => write!(
+                f,
+                "document version {} is newer than the supported version {}",
+                version, STATE_DOCUMENT_VERSION
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {}
+
+impl State {
+    pub fn to_document(&self) -> StateDocument {
+        let variables = Variable::iter()
+            .map(|var| (var, self.variable_values.get(var)))
+            .collect();
+        return StateDocument {
+            version: STATE_DOCUMENT_VERSION,
+            entry_field: self.entry_field.iter().cloned().collect(),
+            previous_entry_field: self.previous_entry_field.iter().cloned().collect(),
+            variables,
+            tape: self.tape.clone(),
+            cursor: Some(self.cursor),
+            angle_mode: self.angle_mode,
+            display_mode: self.display_mode,
+            formulas: self.formulas.clone(),
+            named_variables: self.named_values.clone(),
+        };
+    }
+
+    pub fn from_document(document: StateDocument) -> Result<State, DocumentError> {
+        if document.version > STATE_DOCUMENT_VERSION {
+            return Err(DocumentError::UnsupportedVersion(document.version));
+        }
+        let defaults = State::default().variable_values;
+        let mut state = State::new(|var| match document.variables.get(&var) {
+            Some(value) => *value,
+            None => defaults.get(var),
+        });
+        if Variable::iter().any(|var| !document.variables.contains_key(&var)) {
+            crate::variable_computations::update_unassignable_quantities(&mut state);
+        }
+        state.entry_field = Box::new(document.entry_field.into_iter().collect());
+        state.previous_entry_field = Box::new(document.previous_entry_field.into_iter().collect());
+        state.tape = document.tape;
+        state.angle_mode = document.angle_mode;
+        state.display_mode = document.display_mode;
+        // Formulas of other variables than the memory registers, e.g. from a hand written document
+        state.formulas = document.formulas;
+        state
+            .formulas
+            .retain(|var, _| crate::formulas::can_hold_formula(*var));
+        state.named_values = document.named_variables;
+        state.cursor = document
+            .cursor
+            .unwrap_or(state.entry_field.len())
+            .min(state.entry_field.len());
+        return Ok(state);
+    }
+
+    pub fn to_json(&self) -> String {
+        // Serializing a StateDocument can't fail, there are no maps with non-string keys and the
+        // numbers that JSON can't represent are written as strings
+        return serde_json::to_string_pretty(&self.to_document()).unwrap();
+    }
+
+    pub fn from_json(json: &str) -> Result<State, DocumentError> {
+        let document = serde_json::from_str(json).map_err(DocumentError::Json)?;
+        return State::from_document(document);
+    }
+
+    pub fn to_toml(&self) -> Result<String, DocumentError> {
+        return toml::to_string(&self.to_document()).map_err(DocumentError::TomlWrite);
+    }
+
+    pub fn from_toml(text: &str) -> Result<State, DocumentError> {
+        let document = toml::from_str(text).map_err(DocumentError::TomlRead)?;
+        return State::from_document(document);
+    }
+}
+
+#[cfg(test)]
+mod test_document {
+    use super::*;
+
+    fn example_state() -> State {
+        let mut state = State::default();
+        state.entry_field.push_back(Token::Func(Func::Sin));
+        state.entry_field.push_back(Token::Digit(Digit::Three));
+        state.entry_field.push_back(Token::Variable(Variable::Cas));
+        state.previous_entry_field.push_back(Token::Num(1.5));
+        state
+            .previous_entry_field
+            .push_back(Token::Operator(Operator::Plus));
+        state
+            .previous_entry_field
+            .push_back(Token::Parenth(Parenth::Left));
+        state.variable_values.set(Variable::Ans, 42.);
+        state.variable_values.set(Variable::Temp, f64::NAN);
+        return state;
+    }
+
+    fn assert_same_state(restored: &State, state: &State) {
+        assert_eq!(restored.entry_field, state.entry_field);
+        assert_eq!(restored.previous_entry_field, state.previous_entry_field);
+        for var in Variable::iter() {
+            let (restored_value, value) = (
+                restored.variable_values.get(var),
+                state.variable_values.get(var),
+            );
+            assert!(restored_value == value || (restored_value.is_nan() && value.is_nan()));
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let state = example_state();
+        assert_same_state(&State::from_json(&state.to_json()).unwrap(), &state);
+        assert_same_state(
+            &State::from_toml(&state.to_toml().unwrap()).unwrap(),
+            &state,
+        );
+    }
+
+    #[test]
+    fn test_numbers_that_are_not_finite() {
+        let mut state = example_state();
+        state.entry_field.push_back(Token::Num(f64::INFINITY));
+        state.variable_values.set(Variable::A, f64::NEG_INFINITY);
+        state
+            .named_values
+            .insert(Name::new("fuel").unwrap(), f64::NAN);
+        let json = state.to_json();
+        assert!(json.contains(r#""-inf""#) && json.contains(r#""NaN""#));
+        let restored = State::from_json(&json).unwrap();
+        assert_same_state(&restored, &state);
+        assert!(restored.named_values[&Name::new("fuel").unwrap()].is_nan());
+        assert_same_state(
+            &State::from_toml(&state.to_toml().unwrap()).unwrap(),
+            &state,
+        );
+        assert!(matches!(
+            State::from_json(r#"{"version": 1, "variables": {"Cas": "fast"}}"#),
+            Err(DocumentError::Json(_))
+        ));
+    }
+
+    #[test]
+    fn test_partial_document() {
+        let state =
+            State::from_json(r#"{"version": 1, "variables": {"Altitude": 5500, "Temp": 30}}"#)
+                .unwrap();
+        let defaults = State::default();
+        assert_eq!(state.variable_values.get(Variable::Altitude), 5500.);
+        assert_eq!(
+            state.variable_values.get(Variable::Altimeter),
+            defaults.variable_values.get(Variable::Altimeter)
+        );
+        assert!(state.variable_values.get(Variable::A).is_nan());
+        let density_altitude = state.variable_values.get(Variable::DensAlt);
+        assert!(density_altitude.is_finite() && density_altitude > 5500.);
+    }
+
+    #[test]
+    fn test_versions() {
+        let state = State::from_json(r#"{"version": 1, "variables": {"Cas": 90.0}}"#).unwrap();
+        assert!(state.entry_field.is_empty());
+        assert_eq!(state.variable_values.get(Variable::Cas), 90.);
+        assert_eq!(state.variable_values.get(Variable::Temp), 15.);
+        assert!(matches!(
+            State::from_json(r#"{"version": 2}"#),
+            Err(DocumentError::UnsupportedVersion(2))
+        ));
+        assert!(matches!(
+            State::from_toml("variables = 3"),
+            Err(DocumentError::TomlRead(_))
+        ));
+    }
+}
@@ -1,7 +1,6 @@
 extern crate strum;
 use std::collections::HashMap;
 use strum::IntoEnumIterator;
-use strum_macros::EnumIter;
 
 //An object of type EnumMap<E,T> is a function f from E to T where E is a finite enum such that
 //it's efficient to change the value of f of an individual member of E.
@@ -0,0 +1,112 @@
+use std::fmt;
+
+// Errors that can occur while evaluating the entry field. Every error carries the index of the
+// offending token in the entry field, so that a front end can highlight it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalcError {
+    MismatchedParenthesis { index: usize },
+    MalformedNumber { index: usize },
+    // The argument of a function is outside of its domain, e.g. asin 2
+    DomainError { index: usize },
+    DivisionByZero { index: usize },
+    // An operator or a function is missing an argument
+    EmptyStack { index: usize },
+    // The tokens don't form an expression, e.g. a number is followed by a number
+    MalformedExpression { index: usize },
+    // A formula refers to its own variable, directly or through other formulas
+    CircularFormula { index: usize },
+    // A user-named variable that was deleted
+    UndefinedVariable { index: usize },
+    // Quantities that can't be added or converted, e.g. knots and gallons
+    DimensionMismatch { index: usize },
+}
+
+impl CalcError {
+    pub fn index(&self) -> usize {
+        match *self {
+            CalcError::MismatchedParenthesis { index } => index,
+            CalcError::MalformedNumber { index } => index,
+            CalcError::DomainError { index } => index,
+            CalcError::DivisionByZero { index } => index,
+            CalcError::EmptyStack { index } => index,
+            CalcError::MalformedExpression { index } => index,
+            CalcError::CircularFormula { index } => index,
+            CalcError::UndefinedVariable { index } => index,
+            CalcError::DimensionMismatch { index } => index,
+        }
+    }
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match *self {
+            CalcError::MismatchedParenthesis { .. } => "mismatched parentheses",
+            CalcError::MalformedNumber { .. } => "digits can't be parsed as a number",
+            CalcError::DomainError { .. } => "argument out of range",
+            CalcError::DivisionByZero { .. } => "Can't divide by 0",
+            CalcError::EmptyStack { .. } => "missing argument",
+            CalcError::MalformedExpression { .. } => "not well-formed",
+            CalcError::CircularFormula { .. } => "formula refers to itself",
+            CalcError::UndefinedVariable { .. } => "undefined variable",
+            CalcError::DimensionMismatch { .. } => "units don't match",
+        };
+        return write!(f, "{}", message);
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+// Errors of Name::new
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameError {
+    // Names start with a letter followed by letters, digits and underscores
+    Invalid,
+    TooLong,
+    // The name of a built-in variable or function
+    Reserved,
+}
+
+impl fmt::Display for NameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match *self {
+            NameError::Invalid => "names start with a letter followed by letters, digits and _",
+            NameError::TooLong => "name is too long",
+            NameError::Reserved => "name of a built-in variable or function",
+        };
+        return write!(f, "{}", message);
+    }
+}
+
+impl std::error::Error for NameError {}
+
+// Errors of parse_expression. The position is the byte offset of the offending character in the
+// text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    // The text contains something that isn't a number, operator, parenthesis, function or variable
+    UnknownSymbol { position: usize },
+    // An operator follows an operator, a function or an opening parenthesis
+    MisplacedOperator { position: usize },
+}
+
+impl ParseError {
+    pub fn position(&self) -> usize {
+        match *self {
+            ParseError::UnknownSymbol { position } => position,
+            ParseError::MisplacedOperator { position } => position,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ParseError::UnknownSymbol { position } => write!(f, "unknown symbol at {}", position),
+            ParseError::MisplacedOperator { position } => {
+                write!(f, "misplaced operator at {}", position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
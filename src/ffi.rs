@@ -0,0 +1,290 @@
+// C interface to the calculator, enabled with the "ffi" feature. The declarations are in
+// include/avicalc.h, which is generated from this file with cbindgen.
+#![cfg(feature = "ffi")]
+
+use crate::display::*;
+use crate::error::*;
+use crate::objects::*;
+use crate::tape::*;
+use crate::Calculator;
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_void};
+use strum::IntoEnumIterator;
+
+// Kind of a CalcError, the index of the offending token is passed alongside
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvicalcErrorKind {
+    MismatchedParenthesis,
+    MalformedNumber,
+    DomainError,
+    DivisionByZero,
+    EmptyStack,
+    MalformedExpression,
+    CircularFormula,
+    UndefinedVariable,
+    DimensionMismatch,
+}
+
+// Functions the calculator calls to update the screen. Every function gets user_data as its first
+// argument and any of them may be NULL. Strings are only valid during the call.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct AvicalcCallbacks {
+    pub user_data: *mut c_void,
+    pub entry_changed: Option<extern "C" fn(user_data: *mut c_void, entry: *const c_char)>,
+    pub history_changed:
+        Option<extern "C" fn(user_data: *mut c_void, previous_entry: *const c_char)>,
+    pub variable_changed: Option<extern "C" fn(user_data: *mut c_void, variable: i32, value: f64)>,
+    pub error:
+        Option<extern "C" fn(user_data: *mut c_void, kind: AvicalcErrorKind, token_index: usize)>,
+    // position is the number of characters of the entry field before the cursor
+    pub cursor_moved: Option<extern "C" fn(user_data: *mut c_void, position: usize)>,
+    pub angle_mode_changed: Option<extern "C" fn(user_data: *mut c_void, mode: AngleMode)>,
+    pub display_mode_changed: Option<extern "C" fn(user_data: *mut c_void, mode: DisplayMode)>,
+    // A calculation was added to the tape or the tape was cleared. last_expression is NULL if the
+    // tape is empty.
+    pub tape_changed: Option<
+        extern "C" fn(
+            user_data: *mut c_void,
+            length: usize,
+            last_expression: *const c_char,
+            last_result: f64,
+        ),
+    >,
+    // formula is NULL when the formula of the variable was removed
+    pub formula_changed:
+        Option<extern "C" fn(user_data: *mut c_void, variable: i32, formula: *const c_char)>,
+    // value is NULL when the user-named variable was deleted
+    pub named_variable_changed:
+        Option<extern "C" fn(user_data: *mut c_void, name: *const c_char, value: *const f64)>,
+}
+
+impl DisplaySink for AvicalcCallbacks {
+    fn entry_changed(&mut self, entry: &str) -> () {
+        if let Some(callback) = self.entry_changed {
+            let entry = to_c_string(entry);
+            callback(self.user_data, entry.as_ptr());
+        }
+    }
+    fn cursor_moved(&mut self, position: usize) -> () {
+        if let Some(callback) = self.cursor_moved {
+            callback(self.user_data, position);
+        }
+    }
+    fn angle_mode_changed(&mut self, mode: AngleMode) -> () {
+        if let Some(callback) = self.angle_mode_changed {
+            callback(self.user_data, mode);
+        }
+    }
+    fn display_mode_changed(&mut self, mode: DisplayMode) -> () {
+        if let Some(callback) = self.display_mode_changed {
+            callback(self.user_data, mode);
+        }
+    }
+    fn tape_changed(&mut self, tape: &Tape) -> () {
+        if let Some(callback) = self.tape_changed {
+            match tape.entries().last() {
+                Some(entry) => {
+                    let expression = to_c_string(&entry.expression_as_str());
+                    callback(
+                        self.user_data,
+                        tape.len(),
+                        expression.as_ptr(),
+                        entry.result,
+                    );
+                }
+                None => callback(self.user_data, 0, std::ptr::null(), f64::NAN),
+            }
+        }
+    }
+    fn formula_changed(&mut self, var: Variable, formula: Option<&str>) -> () {
+        if let Some(callback) = self.formula_changed {
+            let formula = formula.map(to_c_string);
+            let pointer = formula
+                .as_ref()
+                .map_or(std::ptr::null(), |text| text.as_ptr());
+            callback(self.user_data, var as i32, pointer);
+        }
+    }
+    fn named_variable_changed(&mut self, name: &str, value: Option<f64>) -> () {
+        if let Some(callback) = self.named_variable_changed {
+            let name = to_c_string(name);
+            let pointer = value
+                .as_ref()
+                .map_or(std::ptr::null(), |value| value as *const f64);
+            callback(self.user_data, name.as_ptr(), pointer);
+        }
+    }
+    fn history_changed(&mut self, previous_entry: &str) -> () {
+        if let Some(callback) = self.history_changed {
+            let previous_entry = to_c_string(previous_entry);
+            callback(self.user_data, previous_entry.as_ptr());
+        }
+    }
+    fn variable_changed(&mut self, var: Variable, value: f64) -> () {
+        if let Some(callback) = self.variable_changed {
+            callback(self.user_data, var as i32, value);
+        }
+    }
+    fn error(&mut self, err: CalcError) -> () {
+        if let Some(callback) = self.error {
+            let kind = match err {
+                CalcError::MismatchedParenthesis { .. } => AvicalcErrorKind::MismatchedParenthesis,
+                CalcError::MalformedNumber { .. } => AvicalcErrorKind::MalformedNumber,
+                CalcError::DomainError { .. } => AvicalcErrorKind::DomainError,
+                CalcError::DivisionByZero { .. } => AvicalcErrorKind::DivisionByZero,
+                CalcError::EmptyStack { .. } => AvicalcErrorKind::EmptyStack,
+                CalcError::MalformedExpression { .. } => AvicalcErrorKind::MalformedExpression,
+                CalcError::CircularFormula { .. } => AvicalcErrorKind::CircularFormula,
+                CalcError::UndefinedVariable { .. } => AvicalcErrorKind::UndefinedVariable,
+                CalcError::DimensionMismatch { .. } => AvicalcErrorKind::DimensionMismatch,
+            };
+            callback(self.user_data, kind, err.index());
+        }
+    }
+}
+
+const NO_CALLBACKS: AvicalcCallbacks = AvicalcCallbacks {
+    user_data: std::ptr::null_mut(),
+    entry_changed: None,
+    history_changed: None,
+    variable_changed: None,
+    error: None,
+    cursor_moved: None,
+    angle_mode_changed: None,
+    display_mode_changed: None,
+    tape_changed: None,
+    formula_changed: None,
+    named_variable_changed: None,
+};
+
+// Opaque handle of a calculator
+pub struct AvicalcCalculator {
+    calculator: Calculator<AvicalcCallbacks>,
+}
+
+fn to_c_string(text: &str) -> CString {
+    // Token::show never produces a nul character
+    return CString::new(text).unwrap_or_default();
+}
+
+fn variable_from_index(index: i32) -> Option<Variable> {
+    if index < 0 {
+        return None;
+    }
+    return Variable::iter().nth(index as usize);
+}
+
+/// Creates a calculator with default values and no callbacks. Free it with avicalc_free.
+#[no_mangle]
+pub extern "C" fn avicalc_new() -> *mut AvicalcCalculator {
+    let handle = AvicalcCalculator {
+        calculator: Calculator::new(State::default(), NO_CALLBACKS),
+    };
+    return Box::into_raw(Box::new(handle));
+}
+
+/// Frees a calculator created with avicalc_new.
+///
+/// # Safety
+/// calculator must be NULL or come from avicalc_new and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn avicalc_free(calculator: *mut AvicalcCalculator) -> () {
+    if !calculator.is_null() {
+        drop(Box::from_raw(calculator));
+    }
+}
+
+/// Replaces the callbacks used to update the screen.
+///
+/// # Safety
+/// calculator must come from avicalc_new and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn avicalc_set_callbacks(
+    calculator: *mut AvicalcCalculator,
+    callbacks: AvicalcCallbacks,
+) -> () {
+    if let Some(handle) = calculator.as_mut() {
+        *handle.calculator.sink_mut() = callbacks;
+    }
+}
+
+/// Presses the key with the given button number, see the *_BUTTON constants. Returns 0 on
+/// success and -1 if the button number is unknown.
+///
+/// # Safety
+/// calculator must come from avicalc_new and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn avicalc_press_button(
+    calculator: *mut AvicalcCalculator,
+    button: u16,
+) -> i32 {
+    match calculator.as_mut() {
+        Some(handle) => match handle.calculator.press_button(button) {
+            Ok(()) => 0,
+            Err(_) => -1,
+        },
+        None => -1,
+    }
+}
+
+/// Calls every callback with the current contents of the screen.
+///
+/// # Safety
+/// calculator must come from avicalc_new and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn avicalc_refresh(calculator: *mut AvicalcCalculator) -> () {
+    if let Some(handle) = calculator.as_mut() {
+        handle.calculator.refresh();
+    }
+}
+
+/// Returns the value of a variable, see AvicalcVariable. Unknown variables give NAN.
+///
+/// # Safety
+/// calculator must come from avicalc_new and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn avicalc_get_variable(
+    calculator: *const AvicalcCalculator,
+    variable: i32,
+) -> f64 {
+    match (calculator.as_ref(), variable_from_index(variable)) {
+        (Some(handle), Some(var)) => handle.calculator.variable(var),
+        _ => f64::NAN,
+    }
+}
+
+#[cfg(test)]
+mod test_ffi {
+    use super::*;
+    use crate::consts::*;
+
+    extern "C" fn count_variables(user_data: *mut c_void, _: i32, _: f64) {
+        unsafe { *(user_data as *mut u32) += 1 };
+    }
+
+    #[test]
+    fn test_c_interface() {
+        let mut changes: u32 = 0;
+        unsafe {
+            let calculator = avicalc_new();
+            avicalc_set_callbacks(
+                calculator,
+                AvicalcCallbacks {
+                    user_data: &mut changes as *mut u32 as *mut c_void,
+                    variable_changed: Some(count_variables),
+                    ..NO_CALLBACKS
+                },
+            );
+            assert_eq!(avicalc_press_button(calculator, EIGHT_BUTTON), 0);
+            assert_eq!(avicalc_press_button(calculator, EQUALS_BUTTON), 0);
+            assert_eq!(avicalc_press_button(calculator, 999), -1);
+            assert_eq!(avicalc_get_variable(calculator, Variable::Ans as i32), 8.);
+            assert!(avicalc_get_variable(calculator, 1000).is_nan());
+            avicalc_free(calculator);
+        }
+        assert_eq!(changes, 2);
+    }
+}
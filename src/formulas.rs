@@ -0,0 +1,240 @@
+use super::*;
+use crate::computation::*;
+use crate::display::*;
+use crate::error::*;
+use crate::objects::*;
+
+use std::collections::{BTreeMap, VecDeque};
+
+// A memory register can hold a formula instead of a number, e.g. A := tas*1.15. Its value is
+// evaluated again after every key press, so it follows the variables the formula refers to.
+
+// Only the memory registers hold formulas, the other variables are set by the calculator
+pub fn can_hold_formula(variable: Variable) -> bool {
+    return matches!(
+        variable,
+        Variable::A | Variable::B | Variable::C | Variable::D
+    );
+}
+
+// Stores the entry field as the formula of the variable. An empty entry field removes the
+// formula and keeps the last value.
+pub fn assign_formula_pressed(
+    variable: Variable,
+    state: &mut State,
+    sink: &mut dyn DisplaySink,
+) -> () {
+    if !can_hold_formula(variable) {
+        return ();
+    }
+    if state.entry_field.is_empty() {
+        if state.formulas.remove(&variable).is_some() {
+            sink.formula_changed(variable, None);
+        }
+        return ();
+    }
+    let formula: Vec<Token> = state.entry_field.iter().cloned().collect();
+    if let Some(index) = find_cycle(&state.formulas, variable, &formula) {
+        sink.error(CalcError::CircularFormula { index });
+        return ();
+    }
+    let value = match compute_entry_field(state) {
+        Err(err) => {
+            sink.error(err);
+            return ();
+        }
+        Ok(value) => value,
+    };
+    sink.formula_changed(variable, Some(&formula_as_str(&formula)));
+    state.formulas.insert(variable, formula);
+    state.variable_values.set(variable, value);
+    state.entry_field.clear();
+    state.cursor = 0;
+    show_entry_field(state, sink);
+    update_formulas(state, sink);
+}
+
+// Assigning a number replaces the formula of the variable
+pub fn remove_formula(variable: Variable, state: &mut State, sink: &mut dyn DisplaySink) -> () {
+    if state.formulas.remove(&variable).is_some() {
+        sink.formula_changed(variable, None);
+    }
+}
+
+pub fn formula_as_str(formula: &[Token]) -> String {
+    let mut field = String::new();
+    for tok in formula.iter() {
+        field.push_str(&tok.show());
+    }
+
+    return field;
+}
+
+// Sends the formulas that differ from the old ones, e.g. after an undo
+pub fn show_changed_formulas(
+    old: &BTreeMap<Variable, Vec<Token>>,
+    state: &State,
+    sink: &mut dyn DisplaySink,
+) -> () {
+    for var in old.keys().filter(|var| !state.formulas.contains_key(var)) {
+        sink.formula_changed(*var, None);
+    }
+    for (var, formula) in state.formulas.iter() {
+        if old.get(var) != Some(formula) {
+            sink.formula_changed(*var, Some(&formula_as_str(formula)));
+        }
+    }
+}
+
+// Returns the index of the token of the formula that leads back to the variable, either directly
+// or through the formulas of other variables
+fn find_cycle(
+    formulas: &BTreeMap<Variable, Vec<Token>>,
+    variable: Variable,
+    formula: &[Token],
+) -> Option<usize> {
+    return formula.iter().position(|tok| match tok.to_inner_var() {
+        Some(var) => depends_on(formulas, var, variable),
+        None => false,
+    });
+}
+
+// The stored formulas never form a cycle, so the recursion ends
+fn depends_on(formulas: &BTreeMap<Variable, Vec<Token>>, var: Variable, target: Variable) -> bool {
+    if var == target {
+        return true;
+    }
+    return match formulas.get(&var) {
+        Some(formula) => formula.iter().any(|tok| match tok.to_inner_var() {
+            Some(next) => depends_on(formulas, next, target),
+            None => false,
+        }),
+        None => false,
+    };
+}
+
+// Evaluates every formula after the formulas it refers to. A formula that can't be evaluated,
+// e.g. because it divides by 0, leaves the variable without a value. The error is shown when
+// the formula stops having a value, its index points into the formula. Only values that differ
+// are set, so that the display isn't updated on every key press.
+pub fn update_formulas(state: &mut State, sink: &mut dyn DisplaySink) -> () {
+    let mut order = Vec::new();
+    for var in state.formulas.keys() {
+        visit(&state.formulas, *var, &mut Vec::new(), &mut order);
+    }
+    for var in order {
+        let formula: VecDeque<Token> = state.formulas[&var].iter().cloned().collect();
+        let old = state.variable_values.get(var);
+        match compute_expression(&formula, state) {
+            Ok(value) => {
+                if old != value {
+                    state.variable_values.set(var, value);
+                }
+            }
+            Err(err) => {
+                if !old.is_nan() {
+                    state.variable_values.set(var, f64::NAN);
+                    sink.error(err);
+                }
+            }
+        }
+    }
+}
+
+// Depth first search, a cycle from a hand written document is cut where it closes
+fn visit(
+    formulas: &BTreeMap<Variable, Vec<Token>>,
+    var: Variable,
+    visiting: &mut Vec<Variable>,
+    order: &mut Vec<Variable>,
+) -> () {
+    if order.contains(&var) || visiting.contains(&var) || !formulas.contains_key(&var) {
+        return ();
+    }
+    visiting.push(var);
+    for next in formulas[&var].iter().filter_map(|tok| tok.to_inner_var()) {
+        visit(formulas, next, visiting, order);
+    }
+    visiting.pop();
+    order.push(var);
+}
+
+#[cfg(test)]
+mod test_formulas {
+    use super::*;
+
+    #[derive(Default)]
+    struct Errors(Vec<CalcError>);
+    impl DisplaySink for Errors {
+        fn error(&mut self, err: CalcError) -> () {
+            self.0.push(err);
+        }
+    }
+
+    fn assign_formula(state: &mut State, var: Variable, text: &str) -> Vec<CalcError> {
+        let mut errors = Errors::default();
+        *state.entry_field = parse_expression(text).unwrap();
+        assign_formula_pressed(var, state, &mut errors);
+        return errors.0;
+    }
+
+    #[test]
+    fn test_formulas_follow_their_inputs() {
+        let mut state = State::default();
+        state.variable_values.set(Variable::B, 10.);
+        assert!(assign_formula(&mut state, Variable::A, "b*2").is_empty());
+        assert!(assign_formula(&mut state, Variable::C, "a+1").is_empty());
+        assert_eq!(state.variable_values.get(Variable::C), 21.);
+
+        state.variable_values.set(Variable::B, 1.);
+        update_formulas(&mut state, &mut Errors::default());
+        assert_eq!(state.variable_values.get(Variable::A), 2.);
+        assert_eq!(state.variable_values.get(Variable::C), 3.);
+
+        assert!(assign_formula(&mut state, Variable::D, "1/b").is_empty());
+        state.variable_values.set(Variable::B, 0.);
+        let mut errors = Errors::default();
+        update_formulas(&mut state, &mut errors);
+        assert!(state.variable_values.get(Variable::D).is_nan());
+        assert_eq!(errors.0, vec![CalcError::DivisionByZero { index: 1 }]);
+        // Only once, not on every key press
+        update_formulas(&mut state, &mut errors);
+        assert_eq!(errors.0.len(), 1);
+    }
+
+    #[test]
+    fn test_cycles_are_refused() {
+        let mut state = State::default();
+        state.variable_values.set(Variable::B, 1.);
+        state.variable_values.set(Variable::C, 1.);
+        assert!(assign_formula(&mut state, Variable::A, "b+1").is_empty());
+        assert!(assign_formula(&mut state, Variable::B, "c+1").is_empty());
+        assert_eq!(
+            assign_formula(&mut state, Variable::C, "2+a"),
+            vec![CalcError::CircularFormula { index: 2 }]
+        );
+        assert_eq!(
+            assign_formula(&mut state, Variable::D, "d"),
+            vec![CalcError::CircularFormula { index: 0 }]
+        );
+        assert_eq!(state.formulas.len(), 2);
+    }
+
+    #[test]
+    fn test_only_memory_registers_hold_formulas() {
+        let mut state = State::default();
+        for var in [Variable::Ans, Variable::Tas].iter() {
+            assert!(assign_formula(&mut state, *var, "1+2").is_empty());
+            assert!(state.formulas.is_empty());
+            assert_eq!(state.entry_field.len(), 3);
+        }
+        let state = State::from_json(
+            r#"{"version": 1, "formulas": {"Tas": [{"Num": 1.0}], "A": [{"Num": 2.0}]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            state.formulas.keys().collect::<Vec<_>>(),
+            vec![&Variable::A]
+        );
+    }
+}
@@ -0,0 +1,316 @@
+// Java interface to the calculator, enabled with the "jni" feature. The native methods belong to
+// the class pro.veezard.avicalc.Backend in java/pro/veezard/avicalc/Backend.java.
+#![cfg(feature = "jni")]
+
+use crate::display::*;
+use crate::error::*;
+use crate::objects::*;
+use crate::tape::*;
+use crate::Calculator;
+
+use jni::objects::{GlobalRef, JClass, JObject, JString, JValue};
+use jni::sys::{jboolean, jdouble, jint, jlong, jstring, JNI_FALSE, JNI_TRUE};
+use jni::{JNIEnv, JavaVM};
+use strum::IntoEnumIterator;
+
+// Forwards the changes of the screen to a Backend.Listener. The callbacks run on the thread that
+// pressed the key. Once a callback throws, the remaining ones are skipped and the exception is
+// rethrown in Java when the native method returns.
+pub struct JavaListener {
+    vm: JavaVM,
+    listener: GlobalRef,
+}
+
+impl JavaListener {
+    // Calls f unless a previous callback has thrown
+    fn with_env<F>(&self, f: F) -> ()
+    where
+        F: FnOnce(&mut JNIEnv, &GlobalRef) -> jni::errors::Result<()>,
+    {
+        if let Ok(mut env) = self.vm.attach_current_thread() {
+            if !env.exception_check().unwrap_or(true) {
+                let _ = f(&mut env, &self.listener);
+            }
+        }
+    }
+
+    fn call_with_string(&self, name: &str, text: &str) -> () {
+        self.with_env(|env, listener| {
+            let string = JObject::from(env.new_string(text)?);
+            env.call_method(
+                listener,
+                name,
+                "(Ljava/lang/String;)V",
+                &[JValue::Object(&string)],
+            )?;
+            return Ok(());
+        });
+    }
+}
+
+impl DisplaySink for JavaListener {
+    fn entry_changed(&mut self, entry: &str) -> () {
+        self.call_with_string("entryChanged", entry);
+    }
+    fn cursor_moved(&mut self, position: usize) -> () {
+        self.with_env(|env, listener| {
+            env.call_method(
+                listener,
+                "cursorMoved",
+                "(I)V",
+                &[JValue::Int(position as jint)],
+            )?;
+            return Ok(());
+        });
+    }
+    fn angle_mode_changed(&mut self, mode: AngleMode) -> () {
+        self.with_env(|env, listener| {
+            env.call_method(
+                listener,
+                "angleModeChanged",
+                "(I)V",
+                &[JValue::Int(mode as jint)],
+            )?;
+            return Ok(());
+        });
+    }
+    fn display_mode_changed(&mut self, mode: DisplayMode) -> () {
+        self.with_env(|env, listener| {
+            env.call_method(
+                listener,
+                "displayModeChanged",
+                "(I)V",
+                &[JValue::Int(mode as jint)],
+            )?;
+            return Ok(());
+        });
+    }
+    fn tape_changed(&mut self, tape: &Tape) -> () {
+        self.with_env(|env, listener| {
+            let (expression, result) = match tape.entries().last() {
+                Some(entry) => (
+                    JObject::from(env.new_string(entry.expression_as_str())?),
+                    entry.result,
+                ),
+                None => (JObject::null(), f64::NAN),
+            };
+            env.call_method(
+                listener,
+                "tapeChanged",
+                "(ILjava/lang/String;D)V",
+                &[
+                    JValue::Int(tape.len() as jint),
+                    JValue::Object(&expression),
+                    JValue::Double(result),
+                ],
+            )?;
+            return Ok(());
+        });
+    }
+    fn formula_changed(&mut self, var: Variable, formula: Option<&str>) -> () {
+        self.with_env(|env, listener| {
+            let formula = match formula {
+                Some(formula) => JObject::from(env.new_string(formula)?),
+                None => JObject::null(),
+            };
+            env.call_method(
+                listener,
+                "formulaChanged",
+                "(ILjava/lang/String;)V",
+                &[JValue::Int(var as jint), JValue::Object(&formula)],
+            )?;
+            return Ok(());
+        });
+    }
+    fn named_variable_changed(&mut self, name: &str, value: Option<f64>) -> () {
+        self.with_env(|env, listener| {
+            let name = JObject::from(env.new_string(name)?);
+            let value = match value {
+                Some(value) => {
+                    env.new_object("java/lang/Double", "(D)V", &[JValue::Double(value)])?
+                }
+                None => JObject::null(),
+            };
+            env.call_method(
+                listener,
+                "namedVariableChanged",
+                "(Ljava/lang/String;Ljava/lang/Double;)V",
+                &[JValue::Object(&name), JValue::Object(&value)],
+            )?;
+            return Ok(());
+        });
+    }
+    fn history_changed(&mut self, previous_entry: &str) -> () {
+        self.call_with_string("historyChanged", previous_entry);
+    }
+    fn variable_changed(&mut self, var: Variable, value: f64) -> () {
+        self.with_env(|env, listener| {
+            env.call_method(
+                listener,
+                "variableChanged",
+                "(ID)V",
+                &[JValue::Int(var as jint), JValue::Double(value)],
+            )?;
+            return Ok(());
+        });
+    }
+    fn error(&mut self, err: CalcError) -> () {
+        self.with_env(|env, listener| {
+            let message = JObject::from(env.new_string(err.to_string())?);
+            env.call_method(
+                listener,
+                "error",
+                "(Ljava/lang/String;I)V",
+                &[JValue::Object(&message), JValue::Int(err.index() as jint)],
+            )?;
+            return Ok(());
+        });
+    }
+}
+
+// The Java side keeps the calculator as a long
+type Handle = Calculator<JavaListener>;
+
+unsafe fn from_handle<'a>(handle: jlong) -> Option<&'a mut Handle> {
+    return (handle as *mut Handle).as_mut();
+}
+
+fn throw_illegal_state(env: &mut JNIEnv) -> () {
+    let _ = env.throw_new(
+        "java/lang/IllegalStateException",
+        "calculator already destroyed",
+    );
+}
+
+#[no_mangle]
+pub extern "system" fn Java_pro_veezard_avicalc_Backend_create(
+    env: JNIEnv,
+    _class: JClass,
+    listener: JObject,
+) -> jlong {
+    let sink = match (env.get_java_vm(), env.new_global_ref(listener)) {
+        (Ok(vm), Ok(listener)) => JavaListener { vm, listener },
+        _ => return 0,
+    };
+    let calculator = Calculator::new(State::default(), sink);
+    return Box::into_raw(Box::new(calculator)) as jlong;
+}
+
+#[no_mangle]
+pub extern "system" fn Java_pro_veezard_avicalc_Backend_destroy(
+    _env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+) -> () {
+    if handle != 0 {
+        drop(unsafe { Box::from_raw(handle as *mut Handle) });
+    }
+}
+
+// Returns false if the button number is unknown
+#[no_mangle]
+pub extern "system" fn Java_pro_veezard_avicalc_Backend_keyPressed(
+    mut env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+    button: jint,
+) -> jboolean {
+    let calculator = match unsafe { from_handle(handle) } {
+        Some(calculator) => calculator,
+        None => {
+            throw_illegal_state(&mut env);
+            return JNI_FALSE;
+        }
+    };
+    if button < 0 || button > u16::MAX as jint {
+        return JNI_FALSE;
+    }
+    match calculator.press_button(button as u16) {
+        Ok(()) => JNI_TRUE,
+        Err(_) => JNI_FALSE,
+    }
+}
+
+#[no_mangle]
+pub extern "system" fn Java_pro_veezard_avicalc_Backend_refresh(
+    mut env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+) -> () {
+    match unsafe { from_handle(handle) } {
+        Some(calculator) => calculator.refresh(),
+        None => throw_illegal_state(&mut env),
+    }
+}
+
+// Returns NaN for unknown variables
+#[no_mangle]
+pub extern "system" fn Java_pro_veezard_avicalc_Backend_getVariable(
+    mut env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+    variable: jint,
+) -> jdouble {
+    let calculator = match unsafe { from_handle(handle) } {
+        Some(calculator) => calculator,
+        None => {
+            throw_illegal_state(&mut env);
+            return f64::NAN;
+        }
+    };
+    if variable < 0 {
+        return f64::NAN;
+    }
+    match Variable::iter().nth(variable as usize) {
+        Some(var) => calculator.variable(var),
+        None => f64::NAN,
+    }
+}
+
+// Returns the state as a JSON document, see the document module
+#[no_mangle]
+pub extern "system" fn Java_pro_veezard_avicalc_Backend_saveState(
+    mut env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+) -> jstring {
+    let calculator = match unsafe { from_handle(handle) } {
+        Some(calculator) => calculator,
+        None => {
+            throw_illegal_state(&mut env);
+            return std::ptr::null_mut();
+        }
+    };
+    match env.new_string(calculator.state().to_json()) {
+        Ok(json) => json.into_raw(),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+// Replaces the state by a document from saveState and shows it, the key presses before it
+// can't be undone anymore. Throws an IllegalArgumentException if the document can't be read.
+#[no_mangle]
+pub extern "system" fn Java_pro_veezard_avicalc_Backend_restoreState(
+    mut env: JNIEnv,
+    _class: JClass,
+    handle: jlong,
+    saved: JString,
+) -> () {
+    let calculator = match unsafe { from_handle(handle) } {
+        Some(calculator) => calculator,
+        None => {
+            throw_illegal_state(&mut env);
+            return ();
+        }
+    };
+    let json: String = match env.get_string(&saved) {
+        Ok(json) => json.into(),
+        Err(_) => return (),
+    };
+    match State::from_json(&json) {
+        Ok(state) => calculator.replace_state(state),
+        Err(err) => {
+            let _ = env.throw_new("java/lang/IllegalArgumentException", err.to_string());
+        }
+    }
+}
@@ -0,0 +1,181 @@
+use crate::consts::*;
+use crate::objects::*;
+
+use std::convert::TryFrom;
+use std::fmt;
+
+// A key of the calculator keyboard. Front ends that still speak in button numbers can convert
+// those with Key::try_from(button_number), and back with u16::try_from(key).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Key {
+    // Adds a token to the entry field at the cursor. Operator::Minus acts as negation where a negation is
+    // expected, e.g. as the first token.
+    Token(Token),
+    Assign(Variable),
+    AddTo(Variable),
+    // Stores the entry field as a formula that is evaluated again whenever its inputs change
+    AssignFormula(Variable),
+    // Like Assign for a user-named variable, which is created if needed
+    AssignNamed(Name),
+    Convert(Unit, Unit),
+    Equals,
+    // Deletes the token before the cursor
+    Backspace,
+    Clear,
+    // Move the cursor by one token, or to the start or end of the entry field
+    CursorLeft,
+    CursorRight,
+    CursorHome,
+    CursorEnd,
+    // Switches between degrees and radians
+    ToggleAngleMode,
+    // Switches between decimal results and H:MM:SS
+    ToggleTimeDisplay,
+    // Steps back and forth through the changes of the entry fields and the variables
+    Undo,
+    Redo,
+    // Replaces the entry field by the expression of a calculation on the tape, see Tape::get
+    Recall(usize),
+    // Adds the result of a calculation on the tape to the entry field
+    InsertResult(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyCodeError {
+    // No key is assigned to this button number
+    UnknownCode(u16),
+    // The key has no button, e.g. a Token::Num or a key of the tape
+    NoCode(Key),
+}
+
+impl fmt::Display for KeyCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            KeyCodeError::UnknownCode(code) => write!(f, "unknown button number {}", code),
+            KeyCodeError::NoCode(key) => write!(f, "{:?} has no button number", key),
+        }
+    }
+}
+
+impl std::error::Error for KeyCodeError {}
+
+impl TryFrom<u16> for Key {
+    type Error = KeyCodeError;
+
+    fn try_from(button: u16) -> Result<Key, KeyCodeError> {
+        let key = match button {
+            EQUALS_BUTTON => Some(Key::Equals),
+            BACKSPACE_BUTTON => Some(Key::Backspace),
+            CLEAR_BUTTON => Some(Key::Clear),
+            UNDO_BUTTON => Some(Key::Undo),
+            REDO_BUTTON => Some(Key::Redo),
+            CURSOR_LEFT_BUTTON => Some(Key::CursorLeft),
+            CURSOR_RIGHT_BUTTON => Some(Key::CursorRight),
+            CURSOR_HOME_BUTTON => Some(Key::CursorHome),
+            CURSOR_END_BUTTON => Some(Key::CursorEnd),
+            ANGLE_MODE_BUTTON => Some(Key::ToggleAngleMode),
+            TIME_DISPLAY_BUTTON => Some(Key::ToggleTimeDisplay),
+            0..=99 => button_number_to_token(button).map(Key::Token),
+            100..=199 => find_variable(&ASSIGN_BUTTONS, button).map(Key::Assign),
+            200..=299 => find_variable(&ADD_BUTTONS, button).map(Key::AddTo),
+            300..=399 => {
+                button_number_to_pair_of_units(button).map(|(from, to)| Key::Convert(from, to))
+            }
+            400..=499 => find_variable(&FORMULA_BUTTONS, button).map(Key::AssignFormula),
+            500..=599 => button_number_to_token(button).map(Key::Token),
+            _ => None,
+        };
+        return key.ok_or(KeyCodeError::UnknownCode(button));
+    }
+}
+
+impl TryFrom<Key> for u16 {
+    type Error = KeyCodeError;
+
+    fn try_from(key: Key) -> Result<u16, KeyCodeError> {
+        let button = match key {
+            Key::Equals => Some(EQUALS_BUTTON),
+            Key::Backspace => Some(BACKSPACE_BUTTON),
+            Key::Clear => Some(CLEAR_BUTTON),
+            Key::Undo => Some(UNDO_BUTTON),
+            Key::Redo => Some(REDO_BUTTON),
+            Key::CursorLeft => Some(CURSOR_LEFT_BUTTON),
+            Key::CursorRight => Some(CURSOR_RIGHT_BUTTON),
+            Key::CursorHome => Some(CURSOR_HOME_BUTTON),
+            Key::CursorEnd => Some(CURSOR_END_BUTTON),
+            Key::ToggleAngleMode => Some(ANGLE_MODE_BUTTON),
+            Key::ToggleTimeDisplay => Some(TIME_DISPLAY_BUTTON),
+            Key::Recall(_) | Key::InsertResult(_) | Key::AssignNamed(_) => None,
+            // The negation shares its button with the minus operator
+            Key::Token(Token::Func(Func::Negation)) => Some(MINUS_BUTTON),
+            Key::Token(Token::Unit(unit)) => UNIT_BUTTONS
+                .iter()
+                .find(|(_, button_unit)| *button_unit == unit)
+                .map(|(number, _)| *number),
+            Key::Token(token) => TOKEN_BUTTONS
+                .iter()
+                .find(|(_, button_token)| *button_token == token)
+                .map(|(number, _)| *number),
+            Key::Assign(var) => find_button(&ASSIGN_BUTTONS, var),
+            Key::AddTo(var) => find_button(&ADD_BUTTONS, var),
+            Key::AssignFormula(var) => find_button(&FORMULA_BUTTONS, var),
+            Key::Convert(from, to) => CONVERSION_BUTTONS
+                .iter()
+                .find(|(_, button_from, button_to)| *button_from == from && *button_to == to)
+                .map(|(number, _, _)| *number),
+        };
+        return button.ok_or(KeyCodeError::NoCode(key));
+    }
+}
+
+fn find_variable(buttons: &[(u16, Variable)], button: u16) -> Option<Variable> {
+    return buttons
+        .iter()
+        .find(|(number, _)| *number == button)
+        .map(|(_, var)| *var);
+}
+
+fn find_button(buttons: &[(u16, Variable)], var: Variable) -> Option<u16> {
+    return buttons
+        .iter()
+        .find(|(_, button_var)| *button_var == var)
+        .map(|(number, _)| *number);
+}
+
+#[cfg(test)]
+mod test_key {
+    use super::*;
+
+    #[test]
+    fn test_button_numbers_round_trip() {
+        let mut known_buttons = 0;
+        for button in 0..2000 {
+            if let Ok(key) = Key::try_from(button) {
+                assert_eq!(u16::try_from(key), Ok(button));
+                known_buttons += 1;
+            }
+        }
+        assert_eq!(
+            known_buttons,
+            11 + TOKEN_BUTTONS.len()
+                + ASSIGN_BUTTONS.len()
+                + ADD_BUTTONS.len()
+                + FORMULA_BUTTONS.len()
+                + CONVERSION_BUTTONS.len()
+                + UNIT_BUTTONS.len()
+        );
+    }
+
+    #[test]
+    fn test_unknown_buttons() {
+        assert_eq!(Key::try_from(99), Err(KeyCodeError::UnknownCode(99)));
+        assert_eq!(Key::try_from(1999), Err(KeyCodeError::UnknownCode(1999)));
+        assert_eq!(Key::try_from(5000), Err(KeyCodeError::UnknownCode(5000)));
+        let key = Key::Token(Token::Num(1.5));
+        assert_eq!(u16::try_from(key), Err(KeyCodeError::NoCode(key)));
+        assert_eq!(
+            u16::try_from(Key::Assign(Variable::Tas)),
+            Err(KeyCodeError::NoCode(Key::Assign(Variable::Tas)))
+        );
+    }
+}
@@ -1,192 +1,1084 @@
+#![allow(clippy::needless_return, clippy::unused_unit)]
 extern crate float_pretty_print;
 
 mod assignments;
 mod computation;
 pub mod consts;
 mod conversions;
+pub mod display;
+pub mod document;
 mod enum_map;
+pub mod error;
+pub mod ffi;
+mod formulas;
+pub mod jni_bindings;
+pub mod key;
 pub mod objects;
+mod parser;
+pub mod tape;
+pub mod undo;
 mod variable_computations;
+pub mod worker;
 
 use crate::assignments::*;
 use crate::computation::*;
-use crate::consts::*;
 use crate::conversions::*;
+use crate::display::*;
+use crate::error::*;
+use crate::formulas::*;
+use crate::key::*;
 use crate::objects::*;
+use crate::parser::*;
+use crate::undo::*;
 
-use std::cell::RefCell;
+pub use crate::parser::parse_expression;
 
-// These two variables hold the persistent state.
-pub static mut STATE: Option<RefCell<Box<State>>> = None;
-pub static mut STRING_DISPLAY_FUNCTIONS: Option<
-    RefCell<Vec<RefCell<Box<dyn FnMut(String) -> ()>>>>,
-> = None;
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::TryFrom;
+use strum::IntoEnumIterator;
 
-// This function gets called when a key gets pressed. The key values are specified in the consts
-// module
-pub fn key_pressed(
-    key: u16,
-    state_: &Option<RefCell<Box<State>>>,
-    string_writers_: &Option<RefCell<Vec<RefCell<Box<dyn FnMut(String) -> ()>>>>>,
-) -> () {
-    let mut state = state_.as_ref().unwrap().borrow_mut();
-    let mut string_writers = string_writers_.as_ref().unwrap().borrow_mut();
-    match key {
-        0..=99 => {
-            token_pressed(key, &mut state, &mut string_writers);
+// A calculator owns its persistent state together with the display sink that shows it, so any
+// number of independent calculators can live in one process.
+pub struct Calculator<S: DisplaySink = NoDisplay> {
+    state: State,
+    sink: S,
+    undo_history: UndoHistory,
+}
+
+impl<S: DisplaySink> Calculator<S> {
+    pub fn new(state: State, sink: S) -> Calculator<S> {
+        return Calculator {
+            state,
+            sink,
+            undo_history: UndoHistory::new(),
+        };
+    }
+
+    // This function gets called when a key gets pressed
+    pub fn press(&mut self, key: Key) -> () {
+        let before = UndoHistory::before_change(&self.state);
+        let state = &mut self.state;
+        let sink = &mut self.sink;
+        match key {
+            Key::Token(token) => token_pressed(token, state, sink),
+            Key::Assign(var) => assign_pressed(var, state, sink),
+            Key::AddTo(var) => add_to_var_pressed(var, state, sink),
+            Key::AssignFormula(var) => assign_formula_pressed(var, state, sink),
+            Key::AssignNamed(name) => assign_named_pressed(name, state, sink),
+            Key::Convert(from, to) => converstion_pressed(from, to, state, sink),
+            Key::Equals => equals_pressed(state, sink),
+            Key::Backspace => backspace_pressed(state, sink),
+            Key::Clear => clear_pressed(state, sink),
+            Key::CursorLeft => move_cursor(cursor_index(state).saturating_sub(1), state, sink),
+            Key::CursorRight => move_cursor(cursor_index(state) + 1, state, sink),
+            Key::CursorHome => move_cursor(0, state, sink),
+            Key::CursorEnd => move_cursor(state.entry_field.len(), state, sink),
+            Key::ToggleAngleMode => {
+                state.angle_mode = state.angle_mode.toggled();
+                sink.angle_mode_changed(state.angle_mode);
+            }
+            Key::ToggleTimeDisplay => {
+                state.display_mode = state.display_mode.toggled();
+                sink.display_mode_changed(state.display_mode);
+            }
+            Key::Recall(index) => recall_pressed(index, state, sink),
+            Key::InsertResult(index) => {
+                if let Some(entry) = state.tape.get(index) {
+                    token_pressed(Token::Num(entry.result), state, sink);
+                }
+            }
+            Key::Undo => {
+                let (formulas, named_values) = (state.formulas.clone(), state.named_values.clone());
+                if self.undo_history.undo(state) {
+                    show_restored(&formulas, &named_values, state, sink);
+                }
+            }
+            Key::Redo => {
+                let (formulas, named_values) = (state.formulas.clone(), state.named_values.clone());
+                if self.undo_history.redo(state) {
+                    show_restored(&formulas, &named_values, state, sink);
+                }
+            }
+        }
+        update_formulas(&mut self.state, &mut self.sink);
+        if !matches!(key, Key::Undo | Key::Redo) {
+            self.undo_history.record(before, &self.state);
+        }
+        self.show_changed_variables();
+    }
+
+    // Same as press but takes one of the button numbers specified in the consts module
+    pub fn press_button(&mut self, button: u16) -> Result<(), KeyCodeError> {
+        self.press(Key::try_from(button)?);
+        return Ok(());
+    }
+
+    // Types the text into the entry field at the cursor as if it was entered key by key, see
+    // parse_expression. On an error the entry field is left as it was.
+    pub fn paste(&mut self, text: &str) -> Result<(), ParseError> {
+        let before = UndoHistory::before_change(&self.state);
+        let (mut prefix, suffix) = split_at_cursor(&self.state);
+        add_expression(&mut prefix, text, &self.state.named_values)?;
+        let cursor = prefix.len();
+        // The error points at the end of the text if the rest of the entry field doesn't fit
+        let entry_field = rejoin(prefix, suffix).ok_or(ParseError::MisplacedOperator {
+            position: text.len(),
+        })?;
+        *self.state.entry_field = entry_field;
+        self.state.cursor = cursor;
+        self.undo_history.record(before, &self.state);
+        show_entry_field(&self.state, &mut self.sink);
+        return Ok(());
+    }
+
+    // Rewrites every field and variable on screen
+    pub fn refresh(&mut self) -> () {
+        show_entry_field(&self.state, &mut self.sink);
+        show_previous_entry_field(&self.state, &mut self.sink);
+        self.sink.angle_mode_changed(self.state.angle_mode);
+        self.sink.display_mode_changed(self.state.display_mode);
+        for (var, formula) in self.state.formulas.iter() {
+            self.sink
+                .formula_changed(*var, Some(&formulas::formula_as_str(formula)));
+        }
+        for (name, value) in self.state.named_values.iter() {
+            self.sink
+                .named_variable_changed(name.as_str(), Some(*value));
+        }
+        self.state.variable_values.take_changed();
+        for var in Variable::iter() {
+            self.sink
+                .variable_changed(var, self.state.variable_values.get(var));
+        }
+    }
+
+    // Sends the variables set since the last update to the display sink. Changes made through
+    // state_mut are shown on the next key press or by calling this function.
+    pub fn show_changed_variables(&mut self) -> () {
+        for var in self.state.variable_values.take_changed() {
+            self.sink
+                .variable_changed(var, self.state.variable_values.get(var));
         }
-        100..=199 => assign_pressed(key, &mut state, &mut string_writers),
-        200..=299 => add_to_var_pressed(key, &mut state, &mut string_writers),
-        300..=399 => converstion_pressed(key, &mut state, &mut string_writers),
-        1000..=1999 => match key {
-            EQUALS_BUTTON => equals_pressed(&mut state, &mut string_writers),
-            BACKSPACE_BUTTON => backspace_pressed(&mut state, &mut string_writers),
-            CLEAR_BUTTON => clear_pressed(&mut state, &mut string_writers),
-            _ => {}
-        },
-        _ => {
-            panic! {"This math should have been exhaustive."}
+    }
+
+    pub fn state(&self) -> &State {
+        return &self.state;
+    }
+    pub fn into_state(self) -> State {
+        return self.state;
+    }
+    pub fn state_mut(&mut self) -> &mut State {
+        return &mut self.state;
+    }
+    // E.g. a restored document. The key presses before it can't be undone anymore.
+    pub fn replace_state(&mut self, state: State) -> () {
+        self.state = state;
+        self.undo_history = UndoHistory::new();
+        self.refresh();
+    }
+
+    // User-named variables, e.g. fuel_left, which can be used in pasted expressions and with
+    // Key::AssignNamed. Changes made here can't be undone.
+    pub fn set_named_variable(&mut self, name: &str, value: f64) -> Result<(), NameError> {
+        let name = Name::new(name)?;
+        self.state.named_values.insert(name, value);
+        self.sink.named_variable_changed(name.as_str(), Some(value));
+        update_formulas(&mut self.state, &mut self.sink);
+        self.show_changed_variables();
+        return Ok(());
+    }
+    pub fn named_variable(&self, name: &str) -> Option<f64> {
+        let name = Name::new(name).ok()?;
+        return self.state.named_values.get(&name).copied();
+    }
+    // In alphabetical order
+    pub fn named_variables(&self) -> impl Iterator<Item = (&str, f64)> {
+        return self
+            .state
+            .named_values
+            .iter()
+            .map(|(name, value)| (name.as_str(), *value));
+    }
+    // Entry fields that use the variable fail to evaluate afterwards. Returns false if there is
+    // no such variable.
+    pub fn delete_named_variable(&mut self, name: &str) -> bool {
+        let removed = match Name::new(name) {
+            Ok(name) => self.state.named_values.remove(&name).is_some(),
+            Err(_) => false,
+        };
+        if removed {
+            self.sink.named_variable_changed(name, None);
+            update_formulas(&mut self.state, &mut self.sink);
+            self.show_changed_variables();
         }
+        return removed;
+    }
+
+    pub fn tape(&self) -> &tape::Tape {
+        return &self.state.tape;
+    }
+    pub fn clear_tape(&mut self) -> () {
+        self.state.tape.clear();
+        self.sink.tape_changed(&self.state.tape);
+    }
+
+    // E.g. to disable the undo and redo keys
+    pub fn can_undo(&self) -> bool {
+        return self.undo_history.can_undo();
+    }
+    pub fn can_redo(&self) -> bool {
+        return self.undo_history.can_redo();
+    }
+
+    pub fn sink(&self) -> &S {
+        return &self.sink;
+    }
+    pub fn sink_mut(&mut self) -> &mut S {
+        return &mut self.sink;
+    }
+
+    pub fn variable(&self, var: Variable) -> f64 {
+        return self.state.variable_values.get(var);
+    }
+
+    pub fn entry_field_as_str(&self) -> String {
+        return entry_field_as_str(&self.state);
+    }
+    pub fn prev_entry_field_as_str(&self) -> String {
+        return prev_entry_field_as_str(&self.state);
     }
 }
 
-pub fn entry_field_as_str(state: &mut Box<State>) -> String {
+pub fn entry_field_as_str(state: &State) -> String {
     let mut field = String::new();
     for tok in state.entry_field.iter() {
-        field.push_str(&*tok.show());
+        field.push_str(&tok.show());
     }
 
     return field;
 }
-pub fn prev_entry_field_as_str(state: &mut Box<State>) -> String {
+pub fn prev_entry_field_as_str(state: &State) -> String {
     let mut field = String::new();
     for tok in state.previous_entry_field.iter() {
-        field.push_str(&*tok.show());
+        field.push_str(&tok.show());
     }
 
     return field;
 }
 
-fn token_pressed(
-    key: u16,
-    state: &mut Box<State>,
-    string_writers: &mut Vec<RefCell<Box<dyn FnMut(String) -> ()>>>,
-) {
-    let token_to_add = match key {
-        MINUS_BUTTON => {
+// Shows what an undo or redo changed, the variables are shown after every key press
+fn show_restored(
+    formulas: &BTreeMap<Variable, Vec<Token>>,
+    named_values: &BTreeMap<Name, f64>,
+    state: &State,
+    sink: &mut dyn DisplaySink,
+) -> () {
+    show_entry_field(state, sink);
+    show_previous_entry_field(state, sink);
+    show_changed_formulas(formulas, state, sink);
+    for name in named_values
+        .keys()
+        .filter(|name| !state.named_values.contains_key(name))
+    {
+        sink.named_variable_changed(name.as_str(), None);
+    }
+    for (name, value) in state.named_values.iter() {
+        let same = named_values
+            .get(name)
+            .is_some_and(|old| old == value || (old.is_nan() && value.is_nan()));
+        if !same {
+            sink.named_variable_changed(name.as_str(), Some(*value));
+        }
+    }
+}
+
+fn token_pressed(token: Token, state: &mut State, sink: &mut dyn DisplaySink) {
+    let (mut prefix, suffix) = split_at_cursor(state);
+    if !add_token(&mut prefix, token) {
+        return ();
+    }
+    let cursor = prefix.len();
+    if let Some(entry_field) = rejoin(prefix, suffix) {
+        *state.entry_field = entry_field;
+        state.cursor = cursor;
+        show_entry_field(state, sink);
+    }
+}
+
+// The cursor within the entry field. The entry field may have been shortened through state_mut
+// without moving the cursor.
+fn cursor_index(state: &State) -> usize {
+    return state.cursor.min(state.entry_field.len());
+}
+
+// The tokens before and after the cursor
+fn split_at_cursor(state: &State) -> (VecDeque<Token>, VecDeque<Token>) {
+    let mut prefix = (*state.entry_field).clone();
+    let suffix = prefix.split_off(cursor_index(state));
+    return (prefix, suffix);
+}
+
+// Adds the tokens after an edit point again, so that the rules of add_token also hold after the
+// edit. Returns None if one of them can't be placed there anymore.
+fn rejoin(mut prefix: VecDeque<Token>, suffix: VecDeque<Token>) -> Option<VecDeque<Token>> {
+    for token in suffix {
+        // Whether a minus is a negation depends on the token before it
+        let token = match token {
+            Token::Func(Func::Negation) => Token::Operator(Operator::Minus),
+            token => token,
+        };
+        if !add_token(&mut prefix, token) {
+            return None;
+        }
+    }
+    return Some(prefix);
+}
+
+fn is_angle_mark(token: &Token) -> bool {
+    return matches!(token, Token::Digit(digit) if digit.angle_mark_position().is_some());
+}
+
+// Adds a token to the end of an entry field the way a key press does, turning a minus into a
+// negation and inserting implied multiplications. Returns false if the token can't be placed
+// there, e.g. an operator after an operator.
+pub(crate) fn add_token(entry_field: &mut VecDeque<Token>, token: Token) -> bool {
+    let token_to_add = match token {
+        Token::Operator(Operator::Minus) => {
             // Minus sign acts as the function negation in certain situations, e.g. as the first token
-            if state.entry_field.is_empty()
-                || *state.entry_field.back().unwrap() == Token::Parenth(Parenth::Left)
-                || matches!(*state.entry_field.back().unwrap(), Token::Operator(_))
-                || matches!(*state.entry_field.back().unwrap(), Token::Func(_))
+            if entry_field.is_empty()
+                || *entry_field.back().unwrap() == Token::Parenth(Parenth::Left)
+                || matches!(*entry_field.back().unwrap(), Token::Operator(_))
+                || matches!(*entry_field.back().unwrap(), Token::Func(_))
+                || *entry_field.back().unwrap() == Token::Comma
+                || *entry_field.back().unwrap() == Token::Digit(Digit::Exponent)
             {
                 Token::Func(Func::Negation)
             } else {
                 Token::Operator(Operator::Minus)
             }
         }
-        button_number => button_number_to_token(button_number),
+        token => token,
     };
 
+    //The exponent marker has to follow the digits of a number without one
+    if token_to_add == Token::Digit(Digit::Exponent) {
+        let mut number = entry_field
+            .iter()
+            .rev()
+            .take_while(|tok| matches!(tok, Token::Digit(_)));
+        if entry_field.back() == Some(&Token::Digit(Digit::Period))
+            || !matches!(entry_field.back(), Some(Token::Digit(_)))
+            || number.any(|tok| {
+                *tok == Token::Digit(Digit::Exponent)
+                    || *tok == Token::Digit(Digit::Colon)
+                    || is_angle_mark(tok)
+            })
+        {
+            return false;
+        }
+    }
+
+    //A colon has to follow the digits of a whole number with at most one colon
+    if token_to_add == Token::Digit(Digit::Colon) {
+        let number: Vec<&Token> = entry_field
+            .iter()
+            .rev()
+            .take_while(|tok| matches!(tok, Token::Digit(_)))
+            .collect();
+        let colons = number
+            .iter()
+            .filter(|tok| ***tok == Token::Digit(Digit::Colon))
+            .count();
+        if number.is_empty()
+            || *number[0] == Token::Digit(Digit::Colon)
+            || colons == 2
+            || number.iter().any(|tok| {
+                **tok == Token::Digit(Digit::Period)
+                    || **tok == Token::Digit(Digit::Exponent)
+                    || is_angle_mark(tok)
+            })
+        {
+            return false;
+        }
+    }
+
+    //An angle mark has to follow a digit and the marks of larger units, as in 47°27'30"
+    if let Token::Digit(mark) = token_to_add {
+        if let Some(position) = mark.angle_mark_position() {
+            let mut number = entry_field
+                .iter()
+                .rev()
+                .take_while(|tok| matches!(tok, Token::Digit(_)));
+            let follows_digit =
+                matches!(entry_field.back(), Some(Token::Digit(digit)) if digit.to_int().is_ok());
+            if !follows_digit
+                || number.any(|tok| match tok {
+                    Token::Digit(Digit::Exponent) | Token::Digit(Digit::Colon) => true,
+                    Token::Digit(digit) => digit
+                        .angle_mark_position()
+                        .is_some_and(|other| other >= position),
+                    _ => false,
+                })
+            {
+                return false;
+            }
+        }
+    }
+
     //Add multiply between symbols if implied
-    if let Some(previous_token) = state.entry_field.back() {
+    if let Some(previous_token) = entry_field.back() {
         if (matches!(token_to_add, Token::Func(_))
+            || matches!(token_to_add, Token::Hemisphere(_))
             || matches!(token_to_add, Token::Digit(_))
             || matches!(token_to_add, Token::Variable(_))
+            || matches!(token_to_add, Token::Named(_))
+            || matches!(token_to_add, Token::Num(_))
             || matches!(token_to_add, Token::Parenth(Parenth::Left)))
             && (matches!(previous_token, Token::Variable(_))
+                || matches!(previous_token, Token::Named(_))
+                || matches!(previous_token, Token::Num(_))
                 || matches!(previous_token, Token::Parenth(Parenth::Right))
+                || *previous_token == Token::Percent
+                || matches!(previous_token, Token::Unit(_))
                 || matches!(previous_token, Token::Digit(_)))
+            && *previous_token != Token::Digit(Digit::Exponent)
             && (!(matches!(token_to_add, Token::Digit(_))
                 && matches!(previous_token, Token::Digit(_))))
         {
-            state
-                .entry_field
-                .push_back(Token::Operator(Operator::Multiply));
+            entry_field.push_back(Token::Operator(Operator::Multiply));
         } else if (matches!(token_to_add, Token::Operator(_))
             && matches!(previous_token, Token::Operator(_)))
         {
-            return ();
+            return false;
         }
     }
     //Check that an operator is placed in an appropriate place
-    if let Some(previous_token) = state.entry_field.back() {
+    if let Some(previous_token) = entry_field.back() {
         if (matches!(token_to_add, Token::Operator(_))
             && (matches!(previous_token, Token::Operator(_))
                 || matches!(previous_token, Token::Func(_))
-                || matches!(previous_token, Token::Parenth(Parenth::Left))))
+                || matches!(previous_token, Token::Parenth(Parenth::Left))
+                || *previous_token == Token::Comma
+                || matches!(previous_token, Token::Hemisphere(_))
+                || *previous_token == Token::Digit(Digit::Exponent)))
         {
-            return;
+            return false;
+        }
+    }
+    //A comma has to follow an argument
+    if token_to_add == Token::Comma {
+        match entry_field.back() {
+            None
+            | Some(Token::Operator(_))
+            | Some(Token::Func(_))
+            | Some(Token::Parenth(Parenth::Left))
+            | Some(Token::Hemisphere(_))
+            | Some(Token::Comma) => return false,
+            _ => (),
         }
     }
 
-    //Add ans at the beginning if line starts with an operator
-    if state.entry_field.is_empty() && matches!(token_to_add, Token::Operator(_)) {
-        state.entry_field.push_back(Token::Variable(Variable::Ans));
+    //A percent sign has to follow a number
+    if token_to_add == Token::Percent {
+        match entry_field.back() {
+            Some(Token::Digit(Digit::Exponent)) => return false,
+            Some(Token::Digit(_))
+            | Some(Token::Num(_))
+            | Some(Token::Variable(_))
+            | Some(Token::Named(_))
+            | Some(Token::Parenth(Parenth::Right)) => (),
+            _ => return false,
+        }
     }
 
-    state.entry_field.push_back(token_to_add);
+    //A unit has to follow a number, or a quantity to convert it, as in 5 nm ft
+    if let Token::Unit(_) = token_to_add {
+        match entry_field.back() {
+            Some(Token::Digit(Digit::Exponent)) => return false,
+            None
+            | Some(Token::Digit(_))
+            | Some(Token::Num(_))
+            | Some(Token::Variable(_))
+            | Some(Token::Named(_))
+            | Some(Token::Parenth(Parenth::Right))
+            | Some(Token::Unit(_)) => (),
+            _ => return false,
+        }
+    }
+
+    //Add ans at the beginning if line starts with an operator or a unit
+    if entry_field.is_empty()
+        && (matches!(token_to_add, Token::Operator(_)) || matches!(token_to_add, Token::Unit(_)))
+    {
+        entry_field.push_back(Token::Variable(Variable::Ans));
+    }
 
-    update_string_field(state, string_writers, ENTRY_FIELD_WRITER);
+    entry_field.push_back(token_to_add);
+    return true;
 }
-fn backspace_pressed(
-    state: &mut Box<State>,
-    string_writers: &mut Vec<RefCell<Box<dyn FnMut(String) -> ()>>>,
-) {
-    if let None = state.entry_field.pop_back() {
+fn backspace_pressed(state: &mut State, sink: &mut dyn DisplaySink) {
+    if state.entry_field.is_empty() {
         state.entry_field = state.previous_entry_field.clone();
         state.previous_entry_field.clear();
+        state.cursor = state.entry_field.len();
         state
             .variable_values
             .set(Variable::Ans, state.variable_values.get(Variable::PrevAns));
         state.variable_values.set(Variable::PrevAns, 0.0);
-        update_string_field(state, string_writers, PREVIOUS_ENTRY_FIELD_WRITER);
+        show_previous_entry_field(state, sink);
+    } else if cursor_index(state) > 0 {
+        let (mut prefix, suffix) = split_at_cursor(state);
+        prefix.pop_back();
+        state.cursor = prefix.len();
+        // Keep the tokens as they are if the rules can't be applied after the deletion
+        *state.entry_field = match rejoin(prefix.clone(), suffix.clone()) {
+            Some(entry_field) => entry_field,
+            None => prefix.into_iter().chain(suffix).collect(),
+        };
     }
-    update_string_field(state, string_writers, ENTRY_FIELD_WRITER);
+    show_entry_field(state, sink);
 }
 
-fn clear_pressed(
-    state: &mut Box<State>,
-    string_writers: &mut Vec<RefCell<Box<dyn FnMut(String) -> ()>>>,
-) {
+fn recall_pressed(index: usize, state: &mut State, sink: &mut dyn DisplaySink) {
+    if let Some(entry) = state.tape.get(index) {
+        *state.entry_field = entry.expression.iter().cloned().collect();
+        state.cursor = state.entry_field.len();
+        show_entry_field(state, sink);
+    }
+}
+
+fn clear_pressed(state: &mut State, sink: &mut dyn DisplaySink) {
     state.entry_field.clear();
-    update_string_field(state, string_writers, ENTRY_FIELD_WRITER);
-}
-
-pub fn refresh_screen(
-    state_: &Option<RefCell<Box<State>>>,
-    string_writers_: &Option<RefCell<Vec<RefCell<Box<dyn FnMut(String) -> ()>>>>>,
-) {
-    let mut state = state_.as_ref().unwrap().borrow_mut();
-    let mut string_writers = string_writers_.as_ref().unwrap().borrow_mut();
-    for field_number in 0..string_writers.len() {
-        update_string_field(&mut state, &mut string_writers, field_number);
-    }
-    state.refresh_variables_display();
-}
-
-pub fn update_string_field(
-    state: &mut Box<State>,
-    string_writers: &mut Vec<RefCell<Box<dyn FnMut(String) -> ()>>>,
-    field: usize,
-) {
-    match field {
-        ENTRY_FIELD_WRITER => {
-            let mut new_entry = String::new();
-            for tok in state.entry_field.iter() {
-                new_entry.push_str(&*tok.show());
-            }
-            string_writers[ENTRY_FIELD_WRITER].borrow_mut()(new_entry);
+    state.cursor = 0;
+    show_entry_field(state, sink);
+}
+
+fn move_cursor(cursor: usize, state: &mut State, sink: &mut dyn DisplaySink) {
+    state.cursor = cursor.min(state.entry_field.len());
+    sink.cursor_moved(cursor_position(state));
+}
+
+// Number of characters of the shown entry field before the cursor
+pub fn cursor_position(state: &State) -> usize {
+    return state
+        .entry_field
+        .iter()
+        .take(cursor_index(state))
+        .map(|tok| tok.show().chars().count())
+        .sum();
+}
+
+pub fn show_entry_field(state: &State, sink: &mut dyn DisplaySink) -> () {
+    sink.entry_changed(&entry_field_as_str(state));
+    sink.cursor_moved(cursor_position(state));
+}
+pub fn show_previous_entry_field(state: &State, sink: &mut dyn DisplaySink) -> () {
+    sink.history_changed(&prev_entry_field_as_str(state));
+}
+
+#[cfg(test)]
+mod test_calculator {
+    use super::*;
+    use crate::consts::*;
+
+    // Remembers everything sent to the display
+    #[derive(Default)]
+    struct Recorder {
+        entry: String,
+        cursor: usize,
+        previous_entry: String,
+        variables: Vec<(Variable, f64)>,
+        errors: Vec<CalcError>,
+        tape_length: usize,
+        angle_mode: AngleMode,
+        formulas: Vec<(Variable, Option<String>)>,
+        named_variables: Vec<(String, Option<f64>)>,
+    }
+
+    impl DisplaySink for Recorder {
+        fn entry_changed(&mut self, entry: &str) -> () {
+            self.entry = entry.to_string();
         }
-        PREVIOUS_ENTRY_FIELD_WRITER => {
-            let mut new_entry = String::new();
-            for tok in state.previous_entry_field.iter() {
-                new_entry.push_str(&*tok.show());
-            }
-            string_writers[PREVIOUS_ENTRY_FIELD_WRITER].borrow_mut()(new_entry);
+        fn cursor_moved(&mut self, position: usize) -> () {
+            self.cursor = position;
+        }
+        fn history_changed(&mut self, previous_entry: &str) -> () {
+            self.previous_entry = previous_entry.to_string();
+        }
+        fn variable_changed(&mut self, var: Variable, value: f64) -> () {
+            self.variables.push((var, value));
+        }
+        fn error(&mut self, err: CalcError) -> () {
+            self.errors.push(err);
+        }
+        fn tape_changed(&mut self, tape: &tape::Tape) -> () {
+            self.tape_length = tape.len();
+        }
+        fn angle_mode_changed(&mut self, mode: AngleMode) -> () {
+            self.angle_mode = mode;
         }
-        _ => {
-            panic!("This match should be exhaustive.")
+        fn formula_changed(&mut self, var: Variable, formula: Option<&str>) -> () {
+            self.formulas
+                .push((var, formula.map(|formula| formula.to_string())));
         }
+        fn named_variable_changed(&mut self, name: &str, value: Option<f64>) -> () {
+            self.named_variables.push((name.to_string(), value));
+        }
+    }
+
+    fn new_calculator() -> Calculator<Recorder> {
+        Calculator::new(State::default(), Recorder::default())
+    }
+
+    #[test]
+    fn test_independent_calculators() {
+        let mut first = new_calculator();
+        let mut second = new_calculator();
+        for button in [TWO_BUTTON, PLUS_BUTTON, THREE_BUTTON, EQUALS_BUTTON].iter() {
+            first.press_button(*button).unwrap();
+        }
+        second.press(Key::Token(Token::Digit(Digit::Nine)));
+        second.press(Key::Token(Token::Operator(Operator::Multiply)));
+        assert_eq!(first.variable(Variable::Ans), 5.);
+        assert_eq!(first.prev_entry_field_as_str(), "2+3");
+        assert!(second.variable(Variable::Ans).is_nan());
+        assert_eq!(second.entry_field_as_str(), "9*");
+    }
+
+    #[test]
+    fn test_display_sink() {
+        let mut calculator = new_calculator();
+        calculator.press_button(SEVEN_BUTTON).unwrap();
+        calculator.press_button(CALIBRATED_AIRSPEED_BUTTON).unwrap();
+        assert_eq!(calculator.sink().entry, "7*cas");
+        assert!(calculator.sink().variables.is_empty());
+        calculator.press_button(EQUALS_BUTTON).unwrap();
+        assert_eq!(calculator.sink().entry, "");
+        assert_eq!(calculator.sink().previous_entry, "7*cas");
+        let changed: Vec<Variable> = calculator.sink().variables.iter().map(|x| x.0).collect();
+        assert_eq!(changed, vec![Variable::PrevAns, Variable::Ans]);
+        assert_eq!(calculator.sink().variables[1].1, 0.);
+
+        calculator.press_button(LEFT_PARENTH_BUTTON).unwrap();
+        calculator.press_button(EQUALS_BUTTON).unwrap();
+        assert_eq!(
+            calculator.sink().errors,
+            vec![CalcError::MismatchedParenthesis { index: 0 }]
+        );
+        assert_eq!(calculator.sink().entry, "(");
+    }
+
+    #[test]
+    fn test_keys() {
+        let mut calculator = new_calculator();
+        calculator.press(Key::Token(Token::Operator(Operator::Minus)));
+        calculator.press(Key::Token(Token::Digit(Digit::Four)));
+        calculator.press(Key::Assign(Variable::Altitude));
+        assert_eq!(calculator.variable(Variable::Altitude), -4.);
+        calculator.press(Key::Token(Token::Digit(Digit::One)));
+        calculator.press(Key::AddTo(Variable::Altitude));
+        assert_eq!(calculator.variable(Variable::Altitude), -3.);
+        calculator.press(Key::Token(Token::Digit(Digit::Two)));
+        calculator.press(Key::Convert(Unit::Gallon, Unit::AvigasPound));
+        assert_eq!(calculator.variable(Variable::Ans), 12.02);
+        assert_eq!(
+            calculator.press_button(2000),
+            Err(KeyCodeError::UnknownCode(2000))
+        );
+    }
+
+    #[test]
+    fn test_tape() {
+        let mut calculator = new_calculator();
+        for button in [SIX_BUTTON, MULTIPLY_BUTTON, SEVEN_BUTTON, EQUALS_BUTTON].iter() {
+            calculator.press_button(*button).unwrap();
+        }
+        for button in [ONE_BUTTON, PLUS_BUTTON, TWO_BUTTON, EQUALS_BUTTON].iter() {
+            calculator.press_button(*button).unwrap();
+        }
+        assert_eq!(calculator.sink().tape_length, 2);
+        let entry = calculator.tape().get(0).unwrap();
+        assert_eq!(entry.expression_as_str(), "6*7");
+        assert_eq!(entry.result, 42.);
+
+        calculator.press(Key::Recall(0));
+        assert_eq!(calculator.sink().entry, "6*7");
+        calculator.press(Key::Clear);
+        calculator.press(Key::InsertResult(1));
+        calculator.press(Key::InsertResult(0));
+        calculator.press(Key::InsertResult(5));
+        assert_eq!(calculator.sink().entry, "3*42");
+        calculator.press(Key::Equals);
+        assert_eq!(calculator.variable(Variable::Ans), 126.);
+
+        calculator.clear_tape();
+        assert_eq!(calculator.sink().tape_length, 0);
+    }
+
+    #[test]
+    fn test_results_that_are_not_finite() {
+        let mut calculator = new_calculator();
+        calculator.paste("a+1").unwrap();
+        calculator.press(Key::Equals);
+        assert_eq!(
+            calculator.sink().errors.last(),
+            Some(&CalcError::DomainError { index: 1 })
+        );
+        calculator.press(Key::Clear);
+        calculator.paste("1E300*1E300").unwrap();
+        calculator.press(Key::Equals);
+        assert_eq!(
+            calculator.sink().errors.last(),
+            Some(&CalcError::DomainError { index: 5 })
+        );
+        assert!(calculator.tape().is_empty());
+
+        // Tapes saved before results were checked
+        calculator.state_mut().tape.push(tape::TapeEntry {
+            expression: vec![Token::Num(1e300), Token::Operator(Operator::Multiply)],
+            result: f64::INFINITY,
+            timestamp: std::time::SystemTime::now(),
+        });
+        let state = State::from_json(&calculator.state().to_json()).unwrap();
+        assert_eq!(state.tape, calculator.state().tape);
+    }
+
+    #[test]
+    fn test_paste() {
+        let mut calculator = new_calculator();
+        calculator.press_button(TWO_BUTTON).unwrap();
+        calculator.paste("0 (1+2)").unwrap();
+        assert_eq!(calculator.sink().entry, "20*(1+2)");
+        assert_eq!(
+            calculator.paste(")*+"),
+            Err(ParseError::MisplacedOperator { position: 2 })
+        );
+        assert_eq!(calculator.entry_field_as_str(), "20*(1+2)");
+        calculator.press(Key::Equals);
+        assert_eq!(calculator.variable(Variable::Ans), 60.);
+        calculator.press(Key::Undo);
+        calculator.press(Key::Undo);
+        assert_eq!(calculator.entry_field_as_str(), "2");
+    }
+
+    #[test]
+    fn test_named_variables() {
+        let mut calculator = new_calculator();
+        assert_eq!(
+            calculator.set_named_variable("sin", 1.),
+            Err(NameError::Reserved)
+        );
+        assert_eq!(
+            calculator.set_named_variable("E12", 1.),
+            Err(NameError::Reserved)
+        );
+        calculator.set_named_variable("vr", 55.).unwrap();
+        calculator.paste("2*500").unwrap();
+        calculator.press(Key::AssignNamed(Name::new("fuel_left").unwrap()));
+        assert_eq!(calculator.named_variable("fuel_left"), Some(1000.));
+
+        calculator.paste("fuel_left/vr2").unwrap();
+        assert_eq!(calculator.entry_field_as_str(), "fuel_left/vr*2");
+        calculator.press(Key::Equals);
+        assert_eq!(calculator.variable(Variable::Ans), 1000. / 55. * 2.);
+        assert_eq!(
+            calculator.named_variables().collect::<Vec<_>>(),
+            vec![("fuel_left", 1000.), ("vr", 55.)]
+        );
+
+        assert!(calculator.delete_named_variable("vr"));
+        assert!(!calculator.delete_named_variable("vr"));
+        assert!(calculator.paste("vr").is_err());
+        calculator.press(Key::Recall(0));
+        calculator.press(Key::Equals);
+        assert_eq!(
+            calculator.sink().errors.last(),
+            Some(&CalcError::UndefinedVariable { index: 2 })
+        );
+        let state = State::from_json(&calculator.state().to_json()).unwrap();
+        assert_eq!(state.named_values, calculator.state().named_values);
+    }
+
+    #[test]
+    fn test_units() {
+        let mut calculator = new_calculator();
+        calculator.paste("100 kph").unwrap();
+        assert_eq!(calculator.entry_field_as_str(), "100\u{200a}kph");
+        calculator.press(Key::Assign(Variable::Cas));
+        assert_eq!(
+            calculator.variable(Variable::Cas),
+            100_f64.convert(Unit::KilometersPerHour, Unit::Knot)
+        );
+        calculator.paste("120 nm / 1:00").unwrap();
+        calculator.press(Key::Assign(Variable::Cas));
+        assert_eq!(calculator.variable(Variable::Cas), 120.);
+        let altitude = calculator.variable(Variable::Altitude);
+        calculator.paste("2*1 gal").unwrap();
+        calculator.press(Key::Assign(Variable::Altitude));
+        assert_eq!(
+            calculator.sink().errors.last(),
+            Some(&CalcError::DimensionMismatch { index: 3 })
+        );
+        assert_eq!(calculator.variable(Variable::Altitude), altitude);
+        calculator.press(Key::Clear);
+        calculator.paste("5+tas").unwrap();
+        calculator.press(Key::Convert(Unit::NauticalMile, Unit::Foot));
+        assert_eq!(
+            calculator.sink().errors.last(),
+            Some(&CalcError::DimensionMismatch { index: 2 })
+        );
+
+        calculator.press(Key::Clear);
+        calculator.paste("cas mph").unwrap();
+        calculator.press(Key::Equals);
+        assert_eq!(
+            calculator.variable(Variable::Ans),
+            120_f64.convert(Unit::Knot, Unit::MilesPerHour)
+        );
+        calculator.press(Key::Token(Token::Unit(Unit::Knot)));
+        assert_eq!(calculator.entry_field_as_str(), "ans\u{200a}kt");
+    }
+
+    #[test]
+    fn test_formulas_follow_named_variables() {
+        let mut calculator = new_calculator();
+        calculator.set_named_variable("fuel", 10.).unwrap();
+        calculator.paste("fuel*2").unwrap();
+        calculator.press(Key::AssignFormula(Variable::A));
+        calculator.set_named_variable("fuel", 15.).unwrap();
+        assert_eq!(calculator.variable(Variable::A), 30.);
+        assert_eq!(
+            calculator.sink().variables.last(),
+            Some(&(Variable::A, 30.))
+        );
+        calculator.delete_named_variable("fuel");
+        assert!(calculator.variable(Variable::A).is_nan());
+        assert_eq!(
+            calculator.sink().errors.last(),
+            Some(&CalcError::UndefinedVariable { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_cursor() {
+        let mut calculator = new_calculator();
+        calculator.paste("12+cas").unwrap();
+        assert_eq!(calculator.sink().cursor, 6);
+        calculator.press(Key::CursorLeft);
+        calculator.press(Key::CursorLeft);
+        assert_eq!(calculator.sink().cursor, 2);
+        calculator.press(Key::Token(Token::Digit(Digit::Five)));
+        assert_eq!(calculator.sink().entry, "125+cas");
+        assert_eq!(calculator.sink().cursor, 3);
+
+        // An operator that doesn't fit before the rest of the entry field is refused
+        calculator.press(Key::Token(Token::Operator(Operator::Minus)));
+        assert_eq!(calculator.entry_field_as_str(), "125+cas");
+
+        // The minus after the cursor turns from a negation into an operator
+        calculator.press(Key::CursorRight);
+        calculator.press(Key::Token(Token::Operator(Operator::Minus)));
+        assert_eq!(calculator.sink().entry, "125+-cas");
+        calculator.press(Key::CursorLeft);
+        calculator.press(Key::Backspace);
+        assert_eq!(calculator.sink().entry, "125-cas");
+        assert_eq!(calculator.sink().cursor, 3);
+
+        // Implied multiplications are added around the edit point
+        calculator.press(Key::CursorEnd);
+        calculator.press(Key::CursorLeft);
+        calculator.press(Key::Token(Token::Digit(Digit::Three)));
+        assert_eq!(calculator.sink().entry, "125-3*cas");
+        assert_eq!(calculator.sink().cursor, 5);
+
+        calculator
+            .state_mut()
+            .variable_values
+            .set(Variable::Cas, 5.);
+        calculator.press(Key::Equals);
+        assert_eq!(calculator.variable(Variable::Ans), 110.);
+        assert_eq!(calculator.sink().cursor, 0);
+        calculator.press(Key::Backspace);
+        assert_eq!(calculator.sink().entry, "125-3*cas");
+        assert_eq!(calculator.sink().cursor, 9);
+    }
+
+    #[test]
+    fn test_angle_mode_key() {
+        let mut calculator = new_calculator();
+        calculator.press_button(ANGLE_MODE_BUTTON).unwrap();
+        assert_eq!(calculator.state().angle_mode, AngleMode::Radians);
+        assert_eq!(calculator.sink().angle_mode, AngleMode::Radians);
+        calculator.paste("2asin 1").unwrap();
+        calculator.press(Key::Equals);
+        assert_eq!(calculator.variable(Variable::Ans), std::f64::consts::PI);
+
+        let saved = calculator.state().to_json();
+        calculator.press(Key::ToggleAngleMode);
+        assert_eq!(calculator.sink().angle_mode, AngleMode::Degrees);
+        let restored = State::from_json(&saved).unwrap();
+        assert_eq!(restored.angle_mode, AngleMode::Radians);
+
+        calculator.press_button(TIME_DISPLAY_BUTTON).unwrap();
+        assert_eq!(calculator.state().display_mode, DisplayMode::Time);
+    }
+
+    #[test]
+    fn test_formula_key() {
+        let mut calculator = new_calculator();
+        calculator.paste("cas*2").unwrap();
+        calculator.press_button(A_FORMULA_BUTTON).unwrap();
+        calculator.paste("60").unwrap();
+        calculator.press(Key::Assign(Variable::Cas));
+        assert_eq!(calculator.variable(Variable::A), 120.);
+        assert!(calculator.sink().variables.contains(&(Variable::A, 120.)));
+
+        let restored = State::from_json(&calculator.state().to_json()).unwrap();
+        assert_eq!(restored.formulas, calculator.state().formulas);
+
+        calculator.press(Key::Undo);
+        assert_eq!(calculator.variable(Variable::A), 0.);
+        calculator.press(Key::Assign(Variable::A));
+        assert_eq!(
+            calculator.sink().formulas,
+            vec![
+                (Variable::A, Some("cas*2".to_string())),
+                (Variable::A, None)
+            ]
+        );
+        assert!(calculator.state().formulas.is_empty());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut calculator = new_calculator();
+        for button in [
+            FOUR_BUTTON,
+            TWO_BUTTON,
+            ALTIMETER_ASSIGN_BUTTON,
+            NINE_BUTTON,
+        ]
+        .iter()
+        {
+            calculator.press_button(*button).unwrap();
+        }
+        // Unknown and ineffective keys don't count as changes
+        calculator.press_button(PLUS_BUTTON).unwrap();
+        calculator.press_button(PLUS_BUTTON).unwrap();
+        assert_eq!(calculator.entry_field_as_str(), "9+");
+
+        calculator.press(Key::Undo);
+        calculator.press(Key::Undo);
+        assert_eq!(calculator.sink().entry, "");
+        assert_eq!(calculator.variable(Variable::Altimeter), 42.);
+        calculator.press(Key::Undo);
+        assert_eq!(calculator.sink().entry, "42");
+        assert_eq!(calculator.variable(Variable::Altimeter), 29.9212);
+        assert!(calculator
+            .sink()
+            .variables
+            .contains(&(Variable::Altimeter, 29.9212)));
+
+        calculator.press(Key::Redo);
+        assert_eq!(calculator.variable(Variable::Altimeter), 42.);
+        assert!(calculator.can_redo());
+        calculator.press_button(ONE_BUTTON).unwrap();
+        assert!(!calculator.can_redo());
+        calculator.press(Key::Redo);
+        assert_eq!(calculator.entry_field_as_str(), "1");
+
+        while calculator.can_undo() {
+            calculator.press(Key::Undo);
+        }
+        assert_eq!(calculator.entry_field_as_str(), "");
+        assert_eq!(calculator.variable(Variable::Altimeter), 29.9212);
+    }
+
+    #[test]
+    fn test_cursor_moves_are_not_undone() {
+        let mut calculator = new_calculator();
+        calculator.paste("12+3").unwrap();
+        calculator.press(Key::CursorLeft);
+        calculator.press(Key::CursorHome);
+        calculator.press(Key::Token(Token::Digit(Digit::Five)));
+        assert_eq!(calculator.entry_field_as_str(), "512+3");
+        calculator.press(Key::Undo);
+        assert_eq!(calculator.entry_field_as_str(), "12+3");
+        assert_eq!(calculator.state().cursor, 0);
+        calculator.press(Key::Undo);
+        assert_eq!(calculator.entry_field_as_str(), "");
+        assert!(!calculator.can_undo());
+    }
+
+    #[test]
+    fn test_cursor_after_outside_edit() {
+        let mut calculator = new_calculator();
+        calculator.paste("123").unwrap();
+        calculator.state_mut().entry_field.clear();
+        calculator.press(Key::Token(Token::Digit(Digit::Four)));
+        assert_eq!(calculator.entry_field_as_str(), "4");
+        assert_eq!(calculator.state().cursor, 1);
+
+        calculator.paste("56").unwrap();
+        calculator.state_mut().entry_field.pop_back();
+        calculator.press(Key::CursorLeft);
+        assert_eq!(calculator.sink().cursor, 1);
+        calculator.state_mut().cursor = 10;
+        calculator.press(Key::Backspace);
+        assert_eq!(calculator.entry_field_as_str(), "4");
+        calculator.state_mut().cursor = 10;
+        calculator.paste("7").unwrap();
+        assert_eq!(calculator.entry_field_as_str(), "47");
+    }
+
+    #[test]
+    fn test_replaced_state_is_not_undone() {
+        let mut calculator = new_calculator();
+        calculator.paste("1+2").unwrap();
+        let state = State::from_json(&State::default().to_json()).unwrap();
+        calculator.replace_state(state);
+        assert!(!calculator.can_undo());
+        calculator.press(Key::Undo);
+        assert_eq!(calculator.entry_field_as_str(), "");
+    }
+
+    #[test]
+    fn test_undo_shows_formulas() {
+        let mut calculator = new_calculator();
+        calculator.paste("cas*2").unwrap();
+        calculator.press(Key::AssignFormula(Variable::A));
+        calculator.press(Key::Undo);
+        assert_eq!(
+            calculator.sink().formulas.last(),
+            Some(&(Variable::A, None))
+        );
+        assert_eq!(calculator.sink().entry, "cas*2");
+        calculator.press(Key::Redo);
+        assert_eq!(
+            calculator.sink().formulas.last(),
+            Some(&(Variable::A, Some("cas*2".to_string())))
+        );
+        assert_eq!(calculator.sink().formulas.len(), 3);
+    }
+
+    #[test]
+    fn test_undo_shows_named_variables() {
+        let mut calculator = new_calculator();
+        calculator.paste("40").unwrap();
+        calculator.press(Key::AssignNamed(Name::new("fuel").unwrap()));
+        calculator.paste("30").unwrap();
+        calculator.press(Key::AssignNamed(Name::new("fuel").unwrap()));
+        calculator.press(Key::Undo);
+        assert_eq!(
+            calculator.sink().named_variables.last(),
+            Some(&("fuel".to_string(), Some(40.)))
+        );
+        calculator.press(Key::Undo);
+        calculator.press(Key::Undo);
+        assert_eq!(
+            calculator.sink().named_variables.last(),
+            Some(&("fuel".to_string(), None))
+        );
+        assert_eq!(calculator.sink().named_variables.len(), 4);
     }
 }
@@ -1,101 +1,101 @@
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, VecDeque};
+use std::convert::TryFrom;
 use std::f64::consts::PI;
 
 use crate::enum_map::*;
-use std::f64::NAN;
+use crate::error::*;
+use crate::tape::*;
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-use std::cell::RefCell;
-use std::rc::Rc;
-
 //Persistent state of the calculator
+#[derive(Clone)]
 pub struct State {
     pub entry_field: Box<VecDeque<Token>>,
     pub previous_entry_field: Box<VecDeque<Token>>,
     pub variable_values: EnumMapOfVars,
+    pub tape: Tape,
+    // Number of tokens of the entry field before the cursor, a larger number stands for its end
+    pub cursor: usize,
+    pub angle_mode: AngleMode,
+    pub display_mode: DisplayMode,
+    // Formulas of the memory registers, see formulas.rs
+    pub formulas: BTreeMap<Variable, Vec<Token>>,
+    // Variables named by the user, e.g. fuel_left, next to the built-in ones
+    pub named_values: BTreeMap<Name, f64>,
 }
 
-impl State {
-    pub fn default<N>(gui_updaters: N) -> State
-    //creates default values for variables but you still need to provide functions for displaying them
-    where
-        N: Fn(Variable) -> Rc<RefCell<Box<dyn FnMut(f64) -> ()>>>,
-    {
-        return State {
-            entry_field: Box::new(VecDeque::new()),
-            previous_entry_field: Box::new(VecDeque::new()),
-            variable_values: EnumMapOfVars::new(
-                |var| match var {
-                    Variable::Altimeter => 29.9212,
-                    Variable::Temp => 15.,
-                    Variable::DewPoint => -65.,
-                    Variable::WindDir => 0.,
-                    Variable::WindSpeed => 0.,
-                    Variable::Altitude => 0.,
-                    Variable::Cas => 0.,
-                    Variable::Tas => 0.,
-                    Variable::GrdSpd => 0.,
-                    Variable::DensAlt => 0.,
-                    Variable::PressAlt => 0.,
-                    Variable::Heading => 0.,
-                    Variable::HeadWind => 0.,
-                    Variable::CrossWind => 0.,
-                    Variable::Course => 0.,
-                    _ => NAN,
-                },
-                gui_updaters,
-            ),
-        };
+impl Default for State {
+    //creates default values for variables
+    fn default() -> State {
+        return State::new(|var| match var {
+            Variable::Altimeter => 29.9212,
+            Variable::Temp => 15.,
+            Variable::DewPoint => -65.,
+            Variable::WindDir => 0.,
+            Variable::WindSpeed => 0.,
+            Variable::Altitude => 0.,
+            Variable::Cas => 0.,
+            Variable::Tas => 0.,
+            Variable::GrdSpd => 0.,
+            Variable::DensAlt => 0.,
+            Variable::PressAlt => 0.,
+            Variable::Heading => 0.,
+            Variable::HeadWind => 0.,
+            Variable::CrossWind => 0.,
+            Variable::Course => 0.,
+            _ => f64::NAN,
+        });
     }
+}
 
+impl State {
     // New state with prescribed variable values
-    pub fn new<N, M>(gui_updaters: N, variable_values: M) -> State
+    pub fn new<M>(variable_values: M) -> State
     where
-        N: Fn(Variable) -> Rc<RefCell<Box<dyn FnMut(f64) -> ()>>>,
         M: Fn(Variable) -> f64,
     {
         return State {
             entry_field: Box::new(VecDeque::new()),
             previous_entry_field: Box::new(VecDeque::new()),
-            variable_values: EnumMapOfVars::new(variable_values, gui_updaters),
+            variable_values: EnumMapOfVars::new(variable_values),
+            tape: Tape::new(),
+            cursor: 0,
+            angle_mode: AngleMode::Degrees,
+            display_mode: DisplayMode::Decimal,
+            formulas: BTreeMap::new(),
+            named_values: BTreeMap::new(),
         };
     }
 
-    pub fn refresh_variables_display(&self) -> () {
-        for var in Variable::iter() {
-            self.variable_values
-                .gui_update_functions
-                .get(var)
-                .borrow_mut()(self.variable_values.values_of_variables.get(var));
-        }
-    }
-
+    #[allow(non_snake_case)]
     pub fn to_enumMap(&self) -> EnumMap<Variable, f64> {
         self.variable_values.values_of_variables.clone()
     }
     pub fn from_enum_map(&mut self, values: EnumMap<Variable, f64>) -> () {
-        self.variable_values.values_of_variables = values;
-        self.refresh_variables_display();
+        for var in Variable::iter() {
+            self.variable_values.set(var, values.get(var));
+        }
     }
 }
 
-// EnumMap of type Variable which also carries the callback functions to execute when values get
-// set.
+// EnumMap of type Variable which also remembers which variables were set since the display was
+// last updated.
+#[derive(Clone)]
 pub struct EnumMapOfVars {
     values_of_variables: EnumMap<Variable, f64>,
-    gui_update_functions: EnumMap<Variable, Rc<RefCell<Box<dyn FnMut(f64) -> ()>>>>,
+    changed_variables: Vec<Variable>,
 }
 
 impl EnumMapOfVars {
-    pub fn new<M, N>(values: M, functions: N) -> EnumMapOfVars
+    pub fn new<M>(values: M) -> EnumMapOfVars
     where
         M: Fn(Variable) -> f64,
-        N: Fn(Variable) -> Rc<RefCell<Box<dyn FnMut(f64) -> ()>>>,
     {
         let var_values = EnumMapOfVars {
             values_of_variables: EnumMap::new(values),
-            gui_update_functions: EnumMap::new(functions),
+            changed_variables: Vec::new(),
         };
         return var_values;
     }
@@ -104,23 +104,98 @@ impl EnumMapOfVars {
     }
     pub fn set(&mut self, key: Variable, value: f64) -> () {
         self.values_of_variables.set(key, value);
-        self.gui_update_functions.get(key).borrow_mut()(value);
+        if !self.changed_variables.contains(&key) {
+            self.changed_variables.push(key);
+        }
+    }
+    // Returns the variables set since the last call, in the order they were first set
+    pub fn take_changed(&mut self) -> Vec<Variable> {
+        return std::mem::take(&mut self.changed_variables);
     }
 }
+// Longest name of a user-named variable in bytes
+pub const NAME_LENGTH: usize = 16;
+
+// Name of a user-named variable: a letter followed by letters, digits and underscores, which
+// isn't the name of a built-in variable or function. The name is stored inline, so that tokens
+// stay Copy.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Name {
+    bytes: [u8; NAME_LENGTH],
+    len: u8,
+}
+
+impl Name {
+    pub fn new(name: &str) -> Result<Name, NameError> {
+        let mut chars = name.chars();
+        let starts_with_letter = chars.next().is_some_and(|c| c.is_ascii_alphabetic());
+        if !starts_with_letter || !chars.all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(NameError::Invalid);
+        }
+        if name.len() > NAME_LENGTH {
+            return Err(NameError::TooLong);
+        }
+        if crate::parser::is_reserved(name) {
+            return Err(NameError::Reserved);
+        }
+        let mut bytes = [0; NAME_LENGTH];
+        bytes[..name.len()].copy_from_slice(name.as_bytes());
+        return Ok(Name {
+            bytes,
+            len: name.len() as u8,
+        });
+    }
+
+    pub fn as_str(&self) -> &str {
+        // Only ASCII is stored
+        return std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap();
+    }
+}
+
+impl std::fmt::Debug for Name {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        return write!(f, "{:?}", self.as_str());
+    }
+}
+
+impl TryFrom<String> for Name {
+    type Error = NameError;
+    fn try_from(name: String) -> Result<Name, NameError> {
+        return Name::new(&name);
+    }
+}
+
+impl From<Name> for String {
+    fn from(name: Name) -> String {
+        return name.as_str().to_string();
+    }
+}
+
 pub trait Show {
     fn show(&self) -> String;
 }
 
 //The entry field of the calculator is a sequence of tokens which can be digits, operators,
 //functions, parentheses, variables,...
-#[derive(Debug, Clone, PartialEq, Copy)]
+#[derive(Debug, Clone, PartialEq, Copy, Serialize, Deserialize)]
 pub enum Token {
     Operator(Operator),
     Digit(Digit),
     Parenth(Parenth),
     Variable(Variable),
     Func(Func),
-    Num(f64),
+    Num(#[serde(with = "crate::document::float_format")] f64),
+    // Separates the arguments of a function with more than one argument, e.g. max(1, 2)
+    Comma,
+    // Postfix percent, 200+10% is 220 and 50*20% is 10
+    Percent,
+    // A variable named by the user, see State::named_values
+    Named(Name),
+    // Starts a coordinate like N47°27.5', which evaluates to signed decimal degrees
+    Hemisphere(Hemisphere),
+    // Follows a number, as in 5 nm, or converts a quantity, as in 5 nm ft
+    Unit(Unit),
 }
 impl Token {
     pub fn to_inner_var(&self) -> Option<Variable> {
@@ -143,10 +218,15 @@ impl Show for Token {
             Token::Variable(x) => x.show(),
             Token::Func(x) => x.show(),
             Token::Num(x) => x.to_string(),
+            Token::Comma => ",".to_string(),
+            Token::Percent => "%".to_string(),
+            Token::Named(x) => x.as_str().to_string(),
+            Token::Hemisphere(x) => x.show(),
+            Token::Unit(x) => "\u{200a}".to_string() + &x.show(),
         }
     }
 }
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
 pub enum Func {
     Negation,
     Sin,
@@ -155,6 +235,18 @@ pub enum Func {
     Arcsin,
     Arccos,
     Arctan,
+    // atan2(y, x) is the angle of the point (x, y)
+    Atan2,
+    Sqrt,
+    Cbrt,
+    // Natural logarithm
+    Ln,
+    Log10,
+    Exp,
+    Abs,
+    Min,
+    Max,
+    Hypot,
 }
 impl Show for Func {
     fn show(&self) -> String {
@@ -166,35 +258,227 @@ impl Show for Func {
             Func::Arcsin => "asin\u{200a}".to_string(),
             Func::Arccos => "acos\u{200a}".to_string(),
             Func::Arctan => "atan\u{200a}".to_string(),
+            Func::Atan2 => "atan2\u{200a}".to_string(),
+            Func::Sqrt => "sqrt\u{200a}".to_string(),
+            Func::Cbrt => "cbrt\u{200a}".to_string(),
+            Func::Ln => "ln\u{200a}".to_string(),
+            Func::Log10 => "log10\u{200a}".to_string(),
+            Func::Exp => "exp\u{200a}".to_string(),
+            Func::Abs => "abs\u{200a}".to_string(),
+            Func::Min => "min\u{200a}".to_string(),
+            Func::Max => "max\u{200a}".to_string(),
+            Func::Hypot => "hypot\u{200a}".to_string(),
         }
     }
 }
 
 impl Func {
-    pub fn apply(&self, x: f64) -> Result<f64, String> {
+    // Number of arguments, which are separated by commas
+    pub fn arity(&self) -> usize {
+        match *self {
+            Func::Atan2 | Func::Min | Func::Max | Func::Hypot => 2,
+            _ => 1,
+        }
+    }
+
+    // args holds arity() arguments. Angles are in the unit of angle_mode. index is the position of
+    // the function in the entry field and is used for error reporting.
+    pub fn apply(
+        &self,
+        args: &[f64],
+        angle_mode: AngleMode,
+        index: usize,
+    ) -> Result<f64, CalcError> {
+        let domain_error = CalcError::DomainError { index };
+        let x = args[0];
         match *self {
             Func::Negation => Ok(-x),
-            Func::Sin => Ok(((x / 180.) * PI).sin()),
-            Func::Cos => Ok(((x / 180.) * PI).cos()),
-            Func::Tan => Ok(((x / 180.) * PI).tan()),
-            Func::Arcsin => (x.asin() * (180. / PI)).to_result("argument out of range".to_string()),
-            Func::Arccos => (x.acos() * (180. / PI)).to_result("argument out of range".to_string()),
-            Func::Arctan => (x.atan() * (180. / PI)).to_result("argument out of range".to_string()),
+            Func::Sin => Ok(angle_mode.to_radians(x).sin()),
+            Func::Cos => Ok(angle_mode.to_radians(x).cos()),
+            Func::Tan => Ok(angle_mode.to_radians(x).tan()),
+            Func::Arcsin => angle_mode.from_radians(x.asin()).to_result(domain_error),
+            Func::Arccos => angle_mode.from_radians(x.acos()).to_result(domain_error),
+            Func::Arctan => angle_mode.from_radians(x.atan()).to_result(domain_error),
+            Func::Atan2 => angle_mode
+                .from_radians(x.atan2(args[1]))
+                .to_result(domain_error),
+            Func::Sqrt => x.sqrt().to_result(domain_error),
+            Func::Cbrt => Ok(x.cbrt()),
+            Func::Ln => x.ln().to_result(domain_error),
+            Func::Log10 => x.log10().to_result(domain_error),
+            Func::Exp => x.exp().to_result(domain_error),
+            Func::Abs => Ok(x.abs()),
+            Func::Min => Ok(x.min(args[1])),
+            Func::Max => Ok(x.max(args[1])),
+            Func::Hypot => Ok(x.hypot(args[1])),
+        }
+    }
+}
+
+// Unit of the arguments of sin, cos and tan and of the results of the arc functions. The
+// discriminants are the angle modes of the C interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[repr(i32)]
+pub enum AngleMode {
+    #[default]
+    Degrees,
+    Radians,
+}
+
+impl AngleMode {
+    pub fn toggled(&self) -> AngleMode {
+        match *self {
+            AngleMode::Degrees => AngleMode::Radians,
+            AngleMode::Radians => AngleMode::Degrees,
+        }
+    }
+    pub fn to_radians(&self, angle: f64) -> f64 {
+        match *self {
+            AngleMode::Degrees => (angle / 180.) * PI,
+            AngleMode::Radians => angle,
+        }
+    }
+    pub fn from_radians(&self, angle: f64) -> f64 {
+        match *self {
+            AngleMode::Degrees => angle * (180. / PI),
+            AngleMode::Radians => angle,
+        }
+    }
+}
+
+impl Show for AngleMode {
+    fn show(&self) -> String {
+        match *self {
+            AngleMode::Degrees => "deg".to_string(),
+            AngleMode::Radians => "rad".to_string(),
         }
     }
 }
 
+// How front ends should show results. Times are entered as 1:35 and computed in hours, in the time
+// mode results are shown as H:MM:SS. The discriminants are the display modes of the C interface.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[repr(i32)]
+pub enum DisplayMode {
+    #[default]
+    Decimal,
+    Time,
+}
+
+impl DisplayMode {
+    pub fn toggled(&self) -> DisplayMode {
+        match *self {
+            DisplayMode::Decimal => DisplayMode::Time,
+            DisplayMode::Time => DisplayMode::Decimal,
+        }
+    }
+    pub fn format(&self, value: f64) -> String {
+        match *self {
+            DisplayMode::Decimal => value.to_string(),
+            DisplayMode::Time => format_time(value),
+        }
+    }
+}
+
+// H:MM:SS of a number of hours, rounded to the second, e.g. 2.75 is 2:45:00
+pub fn format_time(hours: f64) -> String {
+    if !hours.is_finite() {
+        return hours.to_string();
+    }
+    let seconds = (hours.abs() * 3600.).round() as u64;
+    let sign = if hours < 0. && seconds > 0 { "-" } else { "" };
+    return format!(
+        "{}{}:{:02}:{:02}",
+        sign,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Hemisphere {
+    North,
+    South,
+    East,
+    West,
+}
+
+impl Hemisphere {
+    // North and east are positive
+    pub fn sign(&self) -> f64 {
+        match *self {
+            Hemisphere::North | Hemisphere::East => 1.,
+            Hemisphere::South | Hemisphere::West => -1.,
+        }
+    }
+    // Latitudes go up to 90 degrees, longitudes up to 180
+    pub fn max_degrees(&self) -> f64 {
+        match *self {
+            Hemisphere::North | Hemisphere::South => 90.,
+            Hemisphere::East | Hemisphere::West => 180.,
+        }
+    }
+}
+
+impl Show for Hemisphere {
+    fn show(&self) -> String {
+        match *self {
+            Hemisphere::North => "N".to_string(),
+            Hemisphere::South => "S".to_string(),
+            Hemisphere::East => "E".to_string(),
+            Hemisphere::West => "W".to_string(),
+        }
+    }
+}
+
+// Degrees, minutes and seconds of an angle, rounded to a tenth of a second, e.g. 47.4583 is
+// 47°27'29.9"
+pub fn format_dms(degrees: f64) -> String {
+    if !degrees.is_finite() {
+        return degrees.to_string();
+    }
+    let tenths = (degrees.abs() * 36000.).round() as u64;
+    let sign = if degrees < 0. && tenths > 0 { "-" } else { "" };
+    return format!(
+        "{}{}°{:02}'{:02}.{}\"",
+        sign,
+        tenths / 36000,
+        tenths / 600 % 60,
+        tenths / 10 % 60,
+        tenths % 10
+    );
+}
+
+// Signed decimal degrees as a coordinate, e.g. -122.3089 is W122°18'32.0"
+pub fn format_latitude(degrees: f64) -> String {
+    let hemisphere = if degrees < 0. {
+        Hemisphere::South
+    } else {
+        Hemisphere::North
+    };
+    return hemisphere.show() + &format_dms(degrees.abs());
+}
+pub fn format_longitude(degrees: f64) -> String {
+    let hemisphere = if degrees < 0. {
+        Hemisphere::West
+    } else {
+        Hemisphere::East
+    };
+    return hemisphere.show() + &format_dms(degrees.abs());
+}
+
 // This is an interface for a type that itself implements Result-like behavior, e.g. f64 can be
-// NAN
+// NAN or infinite, as in ln 0
 pub trait Resultable {
     type Item;
-    fn to_result(&self, error: String) -> Result<Self::Item, String>;
+    fn to_result(&self, error: CalcError) -> Result<Self::Item, CalcError>;
 }
 
 impl Resultable for f64 {
     type Item = f64;
-    fn to_result(&self, error: String) -> Result<f64, String> {
-        if self.is_nan() {
+    fn to_result(&self, error: CalcError) -> Result<f64, CalcError> {
+        if !self.is_finite() {
             return Err(error);
         } else {
             return Ok(*self);
@@ -202,7 +486,11 @@ impl Resultable for f64 {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, Hash)]
+// The discriminants are the variable numbers of the C interface
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumIter, Hash, Serialize, Deserialize,
+)]
+#[repr(i32)]
 pub enum Variable {
     Ans,
     PrevAns,
@@ -259,13 +547,29 @@ impl Variable {
     pub fn substitute(&self, state: &mut State) -> f64 {
         return state.variable_values.get(*self);
     }
+    // The unit of the values of the variable, None for angles and the registers
+    pub fn unit(&self) -> Option<Unit> {
+        match *self {
+            Variable::Cas
+            | Variable::Tas
+            | Variable::WindSpeed
+            | Variable::HeadWind
+            | Variable::CrossWind
+            | Variable::GrdSpd => Some(Unit::Knot),
+            Variable::Altitude | Variable::DensAlt | Variable::PressAlt => Some(Unit::Foot),
+            Variable::Altimeter => Some(Unit::InHg),
+            Variable::Temp | Variable::DewPoint => Some(Unit::Celcius),
+            _ => None,
+        }
+    }
 }
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Serialize, Deserialize)]
 pub enum Operator {
     Plus,
     Minus,
     Multiply,
     Divide,
+    Power,
 }
 impl Show for Operator {
     fn show(&self) -> String {
@@ -274,12 +578,14 @@ impl Show for Operator {
             Operator::Minus => "-".to_string(),
             Operator::Multiply => "*".to_string(),
             Operator::Divide => "/".to_string(),
+            Operator::Power => "^".to_string(),
         }
     }
 }
 
 impl Operator {
-    pub fn apply(&self, a: f64, b: f64) -> Result<f64, String> {
+    // index is the position of the operator in the entry field and is used for error reporting
+    pub fn apply(&self, a: f64, b: f64, index: usize) -> Result<f64, CalcError> {
         match *self {
             Operator::Plus => Ok(a + b),
             Operator::Minus => Ok(a - b),
@@ -288,17 +594,22 @@ impl Operator {
                 if b.abs() > 0.0000001 {
                     Ok(a / b)
                 } else {
-                    Err("Can't divide by 0".to_string())
+                    Err(CalcError::DivisionByZero { index })
                 }
             }
+            // E.g. a negative number to a fractional power
+            Operator::Power => a.powf(b).to_result(CalcError::DomainError { index }),
         }
     }
+    // Operators with the same precedence are evaluated according to their associativity, e.g.
+    // from left to right in 10-2+3
     pub fn precedence(&self) -> u8 {
         match *self {
             Operator::Plus => 1,
-            Operator::Minus => 2,
-            Operator::Multiply => 3,
-            Operator::Divide => 4,
+            Operator::Minus => 1,
+            Operator::Multiply => 2,
+            Operator::Divide => 2,
+            Operator::Power => 4,
         }
     }
     pub fn associativity(&self) -> Associativity {
@@ -307,17 +618,22 @@ impl Operator {
             Operator::Minus => Associativity::Left,
             Operator::Multiply => Associativity::Left,
             Operator::Divide => Associativity::Left,
+            Operator::Power => Associativity::Right,
         }
     }
 }
 
+// Precedence of the negation compared to Operator::precedence. Operators with a higher precedence
+// bind tighter than a negation before their left operand, e.g. -2^2 is -(2^2).
+pub const NEGATION_PRECEDENCE: u8 = 3;
+
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum Associativity {
     Left,
     Right,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Serialize, Deserialize)]
 pub enum Digit {
     //My digits include period so that a sequence of digits could become a float
     Zero,
@@ -331,6 +647,14 @@ pub enum Digit {
     Eight,
     Nine,
     Period,
+    // Separates the mantissa from the power of ten, as in 1.225E-3
+    Exponent,
+    // Separates hours, minutes and seconds, as in 1:35 or 0:12:30
+    Colon,
+    // Follow the degrees, minutes and seconds of an angle, as in 47°27'30"
+    Degree,
+    Minute,
+    Second,
 }
 
 impl Show for Digit {
@@ -347,6 +671,11 @@ impl Show for Digit {
             Digit::Eight => "8".to_string(),
             Digit::Nine => "9".to_string(),
             Digit::Period => ".".to_string(),
+            Digit::Exponent => "E".to_string(),
+            Digit::Colon => ":".to_string(),
+            Digit::Degree => "°".to_string(),
+            Digit::Minute => "'".to_string(),
+            Digit::Second => "\"".to_string(),
         }
     }
 }
@@ -364,11 +693,25 @@ impl Digit {
             Digit::Eight => Ok(8),
             Digit::Nine => Ok(9),
             Digit::Period => Err("trying to convert period to int".to_string()),
+            Digit::Exponent => Err("trying to convert exponent to int".to_string()),
+            Digit::Colon => Err("trying to convert colon to int".to_string()),
+            Digit::Degree | Digit::Minute | Digit::Second => {
+                Err("trying to convert angle mark to int".to_string())
+            }
+        }
+    }
+    // 0 for degrees, 1 for minutes and 2 for seconds
+    pub fn angle_mark_position(&self) -> Option<i32> {
+        match *self {
+            Digit::Degree => Some(0),
+            Digit::Minute => Some(1),
+            Digit::Second => Some(2),
+            _ => None,
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Copy, Serialize, Deserialize)]
 pub enum Parenth {
     Left,
     Right,
@@ -383,6 +726,7 @@ impl Show for Parenth {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, Serialize, Deserialize)]
 pub enum Unit {
     NauticalMile,
     Mile,
@@ -407,9 +751,98 @@ pub enum Unit {
     Kilopascal,
     Hectopascal,
     Pascal,
+    // Times like 1:30 are in hours
+    Hour,
+}
+
+// What a unit measures, only units of the same dimension can be converted into each other. Fuel
+// pounds are volumes, as they are converted with the density of the fuel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dimension {
+    Dimensionless,
+    Length,
+    Speed,
+    Volume,
+    Mass,
+    Temperature,
+    Pressure,
+    Time,
+}
+
+// Speeds and the lengths they cover in an hour, e.g. knots and nautical miles. Speeds per minute
+// are paired with the length unit they are measured in.
+const SPEEDS_AND_LENGTHS: [(Unit, Unit); 5] = [
+    (Unit::Knot, Unit::NauticalMile),
+    (Unit::MilesPerHour, Unit::Mile),
+    (Unit::KilometersPerHour, Unit::Kilometer),
+    (Unit::FeetPerMinute, Unit::Foot),
+    (Unit::MetersPerMinute, Unit::Meter),
+];
+
+impl Show for Unit {
+    fn show(&self) -> String {
+        match *self {
+            Unit::NauticalMile => "nm".to_string(),
+            Unit::Mile => "mi".to_string(),
+            Unit::Kilometer => "km".to_string(),
+            Unit::Foot => "ft".to_string(),
+            Unit::Meter => "m".to_string(),
+            Unit::Knot => "kt".to_string(),
+            Unit::FeetPerMinute => "fpm".to_string(),
+            Unit::MilesPerHour => "mph".to_string(),
+            Unit::KilometersPerHour => "kph".to_string(),
+            Unit::MetersPerMinute => "mpm".to_string(),
+            Unit::Liter => "l".to_string(),
+            Unit::Gallon => "gal".to_string(),
+            Unit::Kilogram => "kg".to_string(),
+            Unit::Pound => "lb".to_string(),
+            Unit::Celcius => "degC".to_string(),
+            Unit::Kelvin => "K".to_string(),
+            Unit::Fahrenheit => "degF".to_string(),
+            Unit::AvigasPound => "avlb".to_string(),
+            Unit::JetfuelPound => "jetlb".to_string(),
+            Unit::InHg => "inHg".to_string(),
+            Unit::Kilopascal => "kPa".to_string(),
+            Unit::Hectopascal => "hPa".to_string(),
+            Unit::Pascal => "Pa".to_string(),
+            Unit::Hour => "h".to_string(),
+        }
+    }
 }
 
 impl Unit {
+    pub fn dimension(&self) -> Dimension {
+        match *self {
+            Unit::NauticalMile | Unit::Mile | Unit::Kilometer | Unit::Foot | Unit::Meter => {
+                Dimension::Length
+            }
+            Unit::Knot
+            | Unit::FeetPerMinute
+            | Unit::MilesPerHour
+            | Unit::KilometersPerHour
+            | Unit::MetersPerMinute => Dimension::Speed,
+            Unit::Liter | Unit::Gallon | Unit::AvigasPound | Unit::JetfuelPound => {
+                Dimension::Volume
+            }
+            Unit::Kilogram | Unit::Pound => Dimension::Mass,
+            Unit::Celcius | Unit::Kelvin | Unit::Fahrenheit => Dimension::Temperature,
+            Unit::InHg | Unit::Kilopascal | Unit::Hectopascal | Unit::Pascal => Dimension::Pressure,
+            Unit::Hour => Dimension::Time,
+        }
+    }
+    // The length unit of a speed unit and the other way around, see SPEEDS_AND_LENGTHS
+    pub fn length_of_speed(&self) -> Option<Unit> {
+        return SPEEDS_AND_LENGTHS
+            .iter()
+            .find(|(speed, _)| speed == self)
+            .map(|(_, length)| *length);
+    }
+    pub fn speed_of_length(&self) -> Option<Unit> {
+        return SPEEDS_AND_LENGTHS
+            .iter()
+            .find(|(_, length)| length == self)
+            .map(|(speed, _)| *speed);
+    }
     fn to_basic_unit(self, x: f64) -> f64 {
         match self {
             Unit::NauticalMile => x * 1852.,
@@ -421,7 +854,7 @@ impl Unit {
             Unit::FeetPerMinute => x * 0.3048 * 60. / 1000.,
             Unit::MilesPerHour => x * 1.60934,
             Unit::KilometersPerHour => x,
-            Unit::MetersPerMinute => x * 1000. / 60.,
+            Unit::MetersPerMinute => x * 60. / 1000.,
             Unit::Liter => x / 3.78541,
             Unit::Gallon => x,
             Unit::Kilogram => x,
@@ -435,8 +868,10 @@ impl Unit {
             Unit::Kilopascal => x * 10.,
             Unit::Hectopascal => x,
             Unit::Pascal => x / 100.,
+            Unit::Hour => x,
         }
     }
+    #[allow(clippy::wrong_self_convention)]
     fn from_basic_unit(self, x: f64) -> f64 {
         match self {
             Unit::NauticalMile => x / 1852.,
@@ -448,7 +883,7 @@ impl Unit {
             Unit::FeetPerMinute => x * 1000. / (0.3048 * 60.),
             Unit::MilesPerHour => x / 1.60934,
             Unit::KilometersPerHour => x,
-            Unit::MetersPerMinute => x * 60. / 1000.,
+            Unit::MetersPerMinute => x * 1000. / 60.,
             Unit::Liter => x * 3.78541,
             Unit::Gallon => x,
             Unit::Kilogram => x,
@@ -462,9 +897,58 @@ impl Unit {
             Unit::Kilopascal => x / 10.,
             Unit::Hectopascal => x,
             Unit::Pascal => x * 100.,
+            Unit::Hour => x,
         }
     }
 }
+// A value as it flows through the evaluator, e.g. 5 nm. A number without a unit takes the unit
+// of the quantity it is added to. Temperatures are converted as points on their scales, unless
+// they are added to or subtracted from another temperature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub dimension: Dimension,
+    // None for dimensionless numbers
+    pub unit: Option<Unit>,
+}
+
+impl Quantity {
+    pub fn number(value: f64) -> Quantity {
+        return Quantity {
+            value,
+            dimension: Dimension::Dimensionless,
+            unit: None,
+        };
+    }
+    pub fn new(value: f64, unit: Unit) -> Quantity {
+        return Quantity {
+            value,
+            dimension: unit.dimension(),
+            unit: Some(unit),
+        };
+    }
+    // The quantity converted to the unit, a number just gets the unit. None if the dimensions
+    // differ.
+    pub fn in_unit(&self, unit: Unit) -> Option<Quantity> {
+        return match self.unit {
+            None => Some(Quantity::new(self.value, unit)),
+            Some(own) if own.dimension() == unit.dimension() => {
+                Some(Quantity::new(self.value.convert(own, unit), unit))
+            }
+            Some(_) => None,
+        };
+    }
+    // Same as in_unit, but converts a difference, e.g. 18 °F is 10 °C when added to a temperature
+    pub fn difference_in_unit(&self, unit: Unit) -> Option<Quantity> {
+        let converted = self.in_unit(unit)?;
+        let zero = match self.unit {
+            Some(own) => 0_f64.convert(own, unit),
+            None => 0.,
+        };
+        return Some(Quantity::new(converted.value - zero, unit));
+    }
+}
+
 pub trait Convertable {
     fn convert(self, from: Unit, to: Unit) -> Self;
 }
@@ -476,6 +960,7 @@ impl Convertable for f64 {
 
 #[cfg(test)]
 #[test]
+#[allow(clippy::unnecessary_cast)]
 fn test_conversion() {
     assert!(((15.0 as f64).convert(Unit::Liter, Unit::Gallon) - 3.96258).abs() < 0.0001);
     assert!(((15.0 as f64).convert(Unit::Gallon, Unit::Liter) - 56.7812).abs() < 0.0001);
@@ -498,4 +983,20 @@ fn test_conversion() {
     assert!(((15.0 as f64).convert(Unit::Gallon, Unit::AvigasPound) - 90.15).abs() < 0.0001);
     assert!(((15.0 as f64).convert(Unit::Knot, Unit::FeetPerMinute) - 1519.03).abs() < 0.01);
     assert!(((15.0 as f64).convert(Unit::Kilogram, Unit::Pound) - 33.0693).abs() < 0.0001);
+    assert!(
+        ((15.0 as f64).convert(Unit::MetersPerMinute, Unit::KilometersPerHour) - 0.9).abs()
+            < 0.0001
+    );
+    assert!(
+        ((15.0 as f64).convert(Unit::KilometersPerHour, Unit::MetersPerMinute) - 250.).abs()
+            < 0.0001
+    );
+    assert!(
+        ((15.0 as f64)
+            .convert(Unit::MetersPerMinute, Unit::Knot)
+            .convert(Unit::Knot, Unit::MetersPerMinute)
+            - 15.)
+            .abs()
+            < 0.0001
+    );
 }
@@ -0,0 +1,259 @@
+use crate::error::*;
+use crate::objects::*;
+use crate::*;
+
+use std::collections::{BTreeMap, VecDeque};
+use strum::IntoEnumIterator;
+
+// Turns text like "2*cas + (alt-1500)/3" into the entry field that typing it on the keyboard would
+// give. Names are the ones shown in the entry field, e.g. "sin" or "prst", and names or numbers
+// next to each other are multiplied. Whitespace is ignored, but separates names, so that the
+// output of entry_field_as_str can be parsed again. In a coordinate, whitespace separates the
+// degrees, minutes and seconds, as in W122 18 32.
+pub fn parse_expression(text: &str) -> Result<VecDeque<Token>, ParseError> {
+    let mut entry_field = VecDeque::new();
+    add_expression(&mut entry_field, text, &BTreeMap::new())?;
+    return Ok(entry_field);
+}
+
+// Appends the tokens of the text to an entry field, see parse_expression. The names of
+// named_values are recognized as well.
+pub(crate) fn add_expression(
+    entry_field: &mut VecDeque<Token>,
+    text: &str,
+    named_values: &BTreeMap<Name, f64>,
+) -> Result<(), ParseError> {
+    let mut chars = text.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        let token = if c.is_whitespace() {
+            let next_is_digit = chars
+                .clone()
+                .find(|(_, next)| !next.is_whitespace())
+                .is_some_and(|(_, next)| next.is_ascii_digit());
+            match implied_angle_mark(entry_field) {
+                Some(mark) if next_is_digit => Token::Digit(mark),
+                _ => continue,
+            }
+        } else if c.is_alphabetic() {
+            // A user-named variable may contain digits and underscores, e.g. fuel_left
+            let mut lookahead = chars.clone();
+            let mut word = c.to_string();
+            while let Some((_, next)) =
+                lookahead.next_if(|(_, next)| next.is_ascii_alphanumeric() || *next == '_')
+            {
+                word.push(next);
+            }
+            let named = Name::new(&word)
+                .ok()
+                .filter(|name| named_values.contains_key(name));
+            if let Some(name) = named {
+                chars = lookahead;
+                Token::Named(name)
+            } else {
+                let mut name = c.to_string();
+                while let Some((_, next)) = chars.peek() {
+                    if !next.is_alphabetic() {
+                        break;
+                    }
+                    name.push(*next);
+                    chars.next();
+                }
+                // A name may end in digits, e.g. atan2, otherwise the digits are a number
+                let mut lookahead = chars.clone();
+                let mut name_with_digits = name.clone();
+                while let Some((_, next)) = lookahead.next_if(|(_, next)| next.is_ascii_digit()) {
+                    name_with_digits.push(next);
+                }
+                if name_with_digits != name && name_to_token(&name_with_digits).is_some() {
+                    name = name_with_digits;
+                    chars = lookahead;
+                }
+                // An E right after the digits of a number is the exponent marker, as in 6.02E23.
+                // After whitespace it is east, as in 2 E3.
+                if name.eq_ignore_ascii_case("e")
+                    && matches!(entry_field.back(), Some(Token::Digit(_)))
+                    && !text[..position].ends_with(char::is_whitespace)
+                {
+                    Token::Digit(Digit::Exponent)
+                } else {
+                    // A user-named variable followed by digits, e.g. vr2 for vr*2
+                    name_to_token(&name)
+                        .or_else(|| name_to_hemisphere(&name).map(Token::Hemisphere))
+                        .or_else(|| {
+                            let name = Name::new(&name).ok()?;
+                            named_values.get(&name).map(|_| Token::Named(name))
+                        })
+                        .ok_or(ParseError::UnknownSymbol { position })?
+                }
+            }
+        } else {
+            symbol_to_token(c).ok_or(ParseError::UnknownSymbol { position })?
+        };
+        if !add_token(entry_field, token) {
+            return Err(ParseError::MisplacedOperator { position });
+        }
+    }
+    return Ok(());
+}
+
+fn symbol_to_token(c: char) -> Option<Token> {
+    let token = match c {
+        '+' => Token::Operator(Operator::Plus),
+        '-' => Token::Operator(Operator::Minus),
+        '*' => Token::Operator(Operator::Multiply),
+        '/' => Token::Operator(Operator::Divide),
+        '^' => Token::Operator(Operator::Power),
+        ',' => Token::Comma,
+        '%' => Token::Percent,
+        '(' => Token::Parenth(Parenth::Left),
+        ')' => Token::Parenth(Parenth::Right),
+        '.' => Token::Digit(Digit::Period),
+        '°' => Token::Digit(Digit::Degree),
+        '\'' | '′' => Token::Digit(Digit::Minute),
+        '"' | '″' => Token::Digit(Digit::Second),
+        ':' => Token::Digit(Digit::Colon),
+        _ => {
+            let digit = c.to_digit(10)?;
+            return TOKEN_DIGITS
+                .get(digit as usize)
+                .map(|digit| Token::Digit(*digit));
+        }
+    };
+    return Some(token);
+}
+
+const TOKEN_DIGITS: [Digit; 10] = [
+    Digit::Zero,
+    Digit::One,
+    Digit::Two,
+    Digit::Three,
+    Digit::Four,
+    Digit::Five,
+    Digit::Six,
+    Digit::Seven,
+    Digit::Eight,
+    Digit::Nine,
+];
+
+// Names that user-named variables can't take. E followed by digits would be read as an exponent,
+// as in 6.02E23.
+pub(crate) fn is_reserved(name: &str) -> bool {
+    let exponent = name
+        .get(..1)
+        .is_some_and(|first| first.eq_ignore_ascii_case("e"))
+        && name[1..].chars().all(|c| c.is_ascii_digit());
+    return name_to_token(name).is_some() || name_to_hemisphere(name).is_some() || exponent;
+}
+
+fn name_to_hemisphere(name: &str) -> Option<Hemisphere> {
+    let hemisphere = match name.to_uppercase().as_str() {
+        "N" => Hemisphere::North,
+        "S" => Hemisphere::South,
+        "E" => Hemisphere::East,
+        "W" => Hemisphere::West,
+        _ => return None,
+    };
+    return Some(hemisphere);
+}
+
+// The angle mark that whitespace after the digits of a coordinate stands for
+fn implied_angle_mark(entry_field: &VecDeque<Token>) -> Option<Digit> {
+    let number: Vec<Digit> = entry_field
+        .iter()
+        .rev()
+        .map_while(|tok| match tok {
+            Token::Digit(digit) => Some(*digit),
+            _ => None,
+        })
+        .collect();
+    let is_coordinate = matches!(
+        entry_field.iter().rev().nth(number.len()),
+        Some(Token::Hemisphere(_))
+    );
+    let ends_with_digit = number.first().is_some_and(|digit| digit.to_int().is_ok());
+    if !(is_coordinate && ends_with_digit) {
+        return None;
+    }
+    let last_mark = number.iter().find_map(|digit| digit.angle_mark_position());
+    return match last_mark {
+        None => Some(Digit::Degree),
+        Some(0) => Some(Digit::Minute),
+        Some(1) => Some(Digit::Second),
+        _ => None,
+    };
+}
+
+fn name_to_token(name: &str) -> Option<Token> {
+    let name = name.to_lowercase();
+    let variables = Variable::iter()
+        .filter(|var| var.show() == name)
+        .map(Token::Variable);
+    let funcs = Func::iter()
+        .filter(|func| *func != Func::Negation && func.show().trim_end() == name)
+        .map(Token::Func);
+    let units = Unit::iter()
+        .filter(|unit| unit.show().to_lowercase() == name)
+        .map(Token::Unit);
+    return variables.chain(funcs).chain(units).next();
+}
+
+#[cfg(test)]
+mod test_parser {
+    use super::*;
+
+    fn parsed(text: &str) -> String {
+        let state = State {
+            entry_field: Box::new(parse_expression(text).unwrap()),
+            ..State::default()
+        };
+        return entry_field_as_str(&state);
+    }
+
+    #[test]
+    fn test_parse_expression() {
+        assert_eq!(parsed("2*cas + (alt-1500)/3"), "2*cas+(alt-1500)/3");
+        assert_eq!(parsed("2cas(1)"), "2*cas*(1)");
+        assert_eq!(parsed("-3.5 * -TAS"), "-3.5*-tas");
+        assert_eq!(parsed("/2"), "ans/2");
+        assert_eq!(parsed("a b"), "a*b");
+        assert_eq!(parsed("a2+atan2(1, -2)"), "a*2+atan2\u{200a}(1,-2)");
+        assert_eq!(parsed("1.225e-3*2E3"), "1.225E-3*2E3");
+        assert_eq!(parsed("w122 18 32 + e3"), "W122°18'32+E3");
+        assert_eq!(parsed("2 E3"), "2*E3");
+        assert_eq!(parsed("N47°27.5′"), "N47°27.5'");
+        assert_eq!(
+            parse_expression("sin 30").unwrap(),
+            vec![
+                Token::Func(Func::Sin),
+                Token::Digit(Digit::Three),
+                Token::Digit(Digit::Zero)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_parses_again() {
+        let text = "asin(0.5)*prst-crwd/hdg";
+        assert_eq!(parsed(&parsed(text)), parsed(text));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_expression("2 + x"),
+            Err(ParseError::UnknownSymbol { position: 4 })
+        );
+        assert_eq!(
+            parse_expression("2 # 3"),
+            Err(ParseError::UnknownSymbol { position: 2 })
+        );
+        assert_eq!(
+            parse_expression("2E+3"),
+            Err(ParseError::MisplacedOperator { position: 2 })
+        );
+        assert_eq!(
+            parse_expression("2 * / 3"),
+            Err(ParseError::MisplacedOperator { position: 4 })
+        );
+    }
+}
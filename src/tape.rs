@@ -0,0 +1,88 @@
+use crate::objects::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::SystemTime;
+
+// Number of calculations kept on the tape, older ones are dropped
+pub const TAPE_LENGTH: usize = 50;
+
+// A calculation done with the equals key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TapeEntry {
+    pub expression: Vec<Token>,
+    #[serde(with = "crate::document::float_format")]
+    pub result: f64,
+    pub timestamp: SystemTime,
+}
+
+impl TapeEntry {
+    pub fn expression_as_str(&self) -> String {
+        let mut field = String::new();
+        for tok in self.expression.iter() {
+            field.push_str(&tok.show());
+        }
+
+        return field;
+    }
+}
+
+// History of the calculations, oldest first. Indices are positions in this order, so they shift
+// once the tape is full.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tape {
+    entries: VecDeque<TapeEntry>,
+}
+
+impl Tape {
+    pub fn new() -> Tape {
+        return Tape::default();
+    }
+
+    pub fn push(&mut self, entry: TapeEntry) -> () {
+        if self.entries.len() == TAPE_LENGTH {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    pub fn get(&self, index: usize) -> Option<&TapeEntry> {
+        return self.entries.get(index);
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &TapeEntry> {
+        return self.entries.iter();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    pub fn clear(&mut self) -> () {
+        self.entries.clear();
+    }
+}
+
+#[cfg(test)]
+mod test_tape {
+    use super::*;
+
+    #[test]
+    fn test_bounded_tape() {
+        let mut tape = Tape::new();
+        for i in 0..TAPE_LENGTH + 2 {
+            tape.push(TapeEntry {
+                expression: vec![Token::Num(i as f64)],
+                result: i as f64,
+                timestamp: SystemTime::now(),
+            });
+        }
+        assert_eq!(tape.len(), TAPE_LENGTH);
+        assert_eq!(tape.get(0).unwrap().result, 2.);
+        assert_eq!(tape.entries().last().unwrap().expression_as_str(), "51");
+    }
+}
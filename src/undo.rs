@@ -0,0 +1,132 @@
+use crate::enum_map::*;
+use crate::objects::*;
+
+use std::collections::{BTreeMap, VecDeque};
+use strum::IntoEnumIterator;
+
+// Number of key presses that can be undone
+pub const UNDO_LEVELS: usize = 100;
+
+// The entry fields and variable values at some point in time. The cursor is restored with them,
+// but moving it alone isn't a change that can be undone.
+#[derive(Clone)]
+struct Snapshot {
+    entry_field: VecDeque<Token>,
+    previous_entry_field: VecDeque<Token>,
+    variable_values: EnumMap<Variable, f64>,
+    cursor: usize,
+    formulas: BTreeMap<Variable, Vec<Token>>,
+    named_values: BTreeMap<Name, f64>,
+}
+
+impl Snapshot {
+    fn of(state: &State) -> Snapshot {
+        return Snapshot {
+            entry_field: (*state.entry_field).clone(),
+            previous_entry_field: (*state.previous_entry_field).clone(),
+            variable_values: state.to_enumMap(),
+            cursor: state.cursor,
+            formulas: state.formulas.clone(),
+            named_values: state.named_values.clone(),
+        };
+    }
+
+    fn same_as(&self, state: &State) -> bool {
+        return *state.entry_field == self.entry_field
+            && *state.previous_entry_field == self.previous_entry_field
+            && state.formulas == self.formulas
+            && state.named_values.len() == self.named_values.len()
+            && state.named_values.iter().zip(self.named_values.iter()).all(
+                |((name, new), (old_name, old))| {
+                    name == old_name && (old == new || (old.is_nan() && new.is_nan()))
+                },
+            )
+            && Variable::iter().all(|var| {
+                let (old, new) = (
+                    self.variable_values.get(var),
+                    state.variable_values.get(var),
+                );
+                old == new || (old.is_nan() && new.is_nan())
+            });
+    }
+
+    // Only the variables that differ are set, so that only those are shown again
+    fn restore(self, state: &mut State) -> () {
+        for var in Variable::iter() {
+            let (old, new) = (
+                self.variable_values.get(var),
+                state.variable_values.get(var),
+            );
+            if !(old == new || (old.is_nan() && new.is_nan())) {
+                state.variable_values.set(var, old);
+            }
+        }
+        *state.entry_field = self.entry_field;
+        *state.previous_entry_field = self.previous_entry_field;
+        state.cursor = self.cursor;
+        state.formulas = self.formulas;
+        state.named_values = self.named_values;
+    }
+}
+
+// Undo and redo stacks of a calculator. Every key press that changes the state can be undone,
+// pressing a key after undoing drops the redo stack.
+#[derive(Clone, Default)]
+pub struct UndoHistory {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+}
+
+// The state before a key press, see UndoHistory::record
+pub struct PendingChange(Snapshot);
+
+impl UndoHistory {
+    pub fn new() -> UndoHistory {
+        return UndoHistory::default();
+    }
+
+    pub fn before_change(state: &State) -> PendingChange {
+        return PendingChange(Snapshot::of(state));
+    }
+
+    // Remembers the state from before_change if the key press changed anything
+    pub fn record(&mut self, before: PendingChange, state: &State) -> () {
+        if before.0.same_as(state) {
+            return ();
+        }
+        if self.undo.len() == UNDO_LEVELS {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(before.0);
+        self.redo.clear();
+    }
+
+    // Both return false if there is nothing to undo or redo
+    pub fn undo(&mut self, state: &mut State) -> bool {
+        return match self.undo.pop_back() {
+            Some(snapshot) => {
+                self.redo.push(Snapshot::of(state));
+                snapshot.restore(state);
+                true
+            }
+            None => false,
+        };
+    }
+    pub fn redo(&mut self, state: &mut State) -> bool {
+        return match self.redo.pop() {
+            Some(snapshot) => {
+                self.undo.push_back(Snapshot::of(state));
+                snapshot.restore(state);
+                true
+            }
+            None => false,
+        };
+    }
+
+    pub fn can_undo(&self) -> bool {
+        return !self.undo.is_empty();
+    }
+    pub fn can_redo(&self) -> bool {
+        return !self.redo.is_empty();
+    }
+}
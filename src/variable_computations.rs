@@ -1,7 +1,7 @@
 use crate::objects::*;
 use std::f64::consts::PI;
 
-pub fn update_unassignable_quantities(state: &mut Box<State>) {
+pub fn update_unassignable_quantities(state: &mut State) {
     let altimeter = state
         .variable_values
         .get(Variable::Altimeter)
@@ -117,134 +117,100 @@ const RL_BY_GM: f64 = 1. / GM_BY_RL;
 
 #[cfg(test)]
 mod test_var_computations {
-    //Tests should be ran in one thread sequentially.
     use super::*;
-    use crate::*;
-    use std::rc::Rc;
 
     fn compare(a: f64, b: f64, preccision: f64) -> bool {
         (a - b).abs() < preccision
     }
-    unsafe fn initiate_state() {
-        fn gui_update_functions(_: Variable) -> Rc<RefCell<Box<dyn FnMut(f64) -> ()>>> {
-            Rc::new(RefCell::new(Box::new(|_: f64| {})))
-        }
-        let entry_field_writer = Box::new(|_: String| {});
-        let previous_entry_field_writer = Box::new(|_: String| {});
+    fn initiate_state() -> State {
         //--------------Initialize state of the calculator
-        STATE = Some(RefCell::new(Box::new(State::default(gui_update_functions))));
-
-        STRING_DISPLAY_FUNCTIONS = Some(RefCell::new(vec![
-            RefCell::new(entry_field_writer),
-            RefCell::new(previous_entry_field_writer),
-        ]));
+        State::default()
     }
 
     #[test]
     fn test_pressure_altitude() {
-        unsafe {
-            initiate_state();
-            let mut state = &mut STATE.as_ref().unwrap().borrow_mut();
-            state.variable_values.set(Variable::Altimeter, 29.);
-            state.variable_values.set(Variable::Altitude, 8000.);
-            update_unassignable_quantities(&mut state);
-            assert!(compare(
-                state.variable_values.get(Variable::PressAlt),
-                8862.,
-                10.
-            ));
-        }
+        let mut state = initiate_state();
+        state.variable_values.set(Variable::Altimeter, 29.);
+        state.variable_values.set(Variable::Altitude, 8000.);
+        update_unassignable_quantities(&mut state);
+        assert!(compare(
+            state.variable_values.get(Variable::PressAlt),
+            8862.,
+            10.
+        ));
     }
     #[test]
     fn test_density_altitude() {
-        unsafe {
-            initiate_state();
-            let mut state = &mut STATE.as_ref().unwrap().borrow_mut();
-            state.variable_values.set(Variable::Altimeter, 28.);
-            state.variable_values.set(Variable::Altitude, 15000.);
-            state.variable_values.set(Variable::Temp, 5.);
-            state.variable_values.set(Variable::DewPoint, 25.);
-            update_unassignable_quantities(&mut state);
-            assert!(compare(
-                state.variable_values.get(Variable::DensAlt),
-                20125.,
-                10.
-            ));
-        }
+        let mut state = initiate_state();
+        state.variable_values.set(Variable::Altimeter, 28.);
+        state.variable_values.set(Variable::Altitude, 15000.);
+        state.variable_values.set(Variable::Temp, 5.);
+        state.variable_values.set(Variable::DewPoint, 25.);
+        update_unassignable_quantities(&mut state);
+        assert!(compare(
+            state.variable_values.get(Variable::DensAlt),
+            20125.,
+            10.
+        ));
     }
     #[test]
     fn test_tas() {
-        unsafe {
-            initiate_state();
-            let mut state = &mut STATE.as_ref().unwrap().borrow_mut();
-            state.variable_values.set(Variable::Altimeter, 28.5);
-            state.variable_values.set(Variable::Altitude, 11000.);
-            state.variable_values.set(Variable::Temp, 0.);
-            state.variable_values.set(Variable::Cas, 65.);
-            update_unassignable_quantities(&mut state);
-            assert!(compare(state.variable_values.get(Variable::Tas), 80., 1.));
-        }
+        let mut state = initiate_state();
+        state.variable_values.set(Variable::Altimeter, 28.5);
+        state.variable_values.set(Variable::Altitude, 11000.);
+        state.variable_values.set(Variable::Temp, 0.);
+        state.variable_values.set(Variable::Cas, 65.);
+        update_unassignable_quantities(&mut state);
+        assert!(compare(state.variable_values.get(Variable::Tas), 80., 1.));
     }
     #[test]
     fn test_cross_wind() {
-        unsafe {
-            initiate_state();
-            let mut state = &mut STATE.as_ref().unwrap().borrow_mut();
-            update_unassignable_quantities(&mut state);
-            state.variable_values.set(Variable::WindDir, 100.);
-            state.variable_values.set(Variable::WindSpeed, 24.);
-            state.variable_values.set(Variable::Course, 210.);
-            update_unassignable_quantities(&mut state);
-            assert!(compare(
-                state.variable_values.get(Variable::CrossWind),
-                -22.6,
-                0.1
-            ));
-        }
+        let mut state = initiate_state();
+        update_unassignable_quantities(&mut state);
+        state.variable_values.set(Variable::WindDir, 100.);
+        state.variable_values.set(Variable::WindSpeed, 24.);
+        state.variable_values.set(Variable::Course, 210.);
+        update_unassignable_quantities(&mut state);
+        assert!(compare(
+            state.variable_values.get(Variable::CrossWind),
+            -22.6,
+            0.1
+        ));
     }
     #[test]
     fn test_head_wind() {
-        unsafe {
-            initiate_state();
-            let mut state = &mut STATE.as_ref().unwrap().borrow_mut();
-            state.variable_values.set(Variable::WindDir, 100.);
-            state.variable_values.set(Variable::WindSpeed, 24.);
-            state.variable_values.set(Variable::Course, 210.);
-            update_unassignable_quantities(&mut state);
-            assert!(compare(
-                state.variable_values.get(Variable::HeadWind),
-                -8.2,
-                0.1
-            ));
-        }
+        let mut state = initiate_state();
+        state.variable_values.set(Variable::WindDir, 100.);
+        state.variable_values.set(Variable::WindSpeed, 24.);
+        state.variable_values.set(Variable::Course, 210.);
+        update_unassignable_quantities(&mut state);
+        assert!(compare(
+            state.variable_values.get(Variable::HeadWind),
+            -8.2,
+            0.1
+        ));
     }
     #[test]
     fn test_ground_speed() {
-        unsafe {
-            initiate_state();
-            let mut state = &mut STATE.as_ref().unwrap().borrow_mut();
-            update_unassignable_quantities(&mut state);
-            //assert!(compare(state.variable_values.get(Variable::GrdSpd) , 0. , 0.001));
-        }
+        let mut state = initiate_state();
+        update_unassignable_quantities(&mut state);
+        //assert!(compare(state.variable_values.get(Variable::GrdSpd) , 0. , 0.001));
     }
     #[test]
     fn test_heading() {
-        unsafe {
-            initiate_state();
-            let mut state = &mut STATE.as_ref().unwrap().borrow_mut();
-            state.variable_values.set(Variable::WindDir, 100.);
-            state.variable_values.set(Variable::WindSpeed, 24.);
-            state.variable_values.set(Variable::Course, 210.);
-            state.variable_values.set(Variable::Altimeter, 28.5);
-            state.variable_values.set(Variable::Altitude, 11000.);
-            state.variable_values.set(Variable::Temp, 0.);
-            state.variable_values.set(Variable::Cas, 65.);
-            update_unassignable_quantities(&mut state);
-            assert!(compare(
-                state.variable_values.get(Variable::Heading),
-                210. - 16.40,
-                0.1
-            ));
-        }
+        let mut state = initiate_state();
+        state.variable_values.set(Variable::WindDir, 100.);
+        state.variable_values.set(Variable::WindSpeed, 24.);
+        state.variable_values.set(Variable::Course, 210.);
+        state.variable_values.set(Variable::Altimeter, 28.5);
+        state.variable_values.set(Variable::Altitude, 11000.);
+        state.variable_values.set(Variable::Temp, 0.);
+        state.variable_values.set(Variable::Cas, 65.);
+        update_unassignable_quantities(&mut state);
+        assert!(compare(
+            state.variable_values.get(Variable::Heading),
+            210. - 16.40,
+            0.1
+        ));
     }
 }
@@ -0,0 +1,72 @@
+use crate::display::*;
+use crate::key::*;
+use crate::objects::*;
+use crate::Calculator;
+
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+// A calculator running on a thread of its own. Keys are sent to it through `keys` and the changes
+// of the screen come back through `events`, starting with a full refresh. Dropping `keys` stops the
+// thread, which then hands back the state.
+pub struct CalculatorThread {
+    pub keys: Sender<Key>,
+    pub events: Receiver<DisplayEvent>,
+    pub handle: JoinHandle<State>,
+}
+
+pub fn spawn_calculator(state: State) -> CalculatorThread {
+    let (keys, key_receiver) = channel::<Key>();
+    let (event_sender, events) = channel::<DisplayEvent>();
+    let handle = thread::spawn(move || {
+        let mut calculator = Calculator::new(state, event_sender);
+        calculator.refresh();
+        for key in key_receiver {
+            calculator.press(key);
+        }
+        return calculator.into_state();
+    });
+    return CalculatorThread {
+        keys,
+        events,
+        handle,
+    };
+}
+
+#[cfg(test)]
+mod test_worker {
+    use super::*;
+    use crate::consts::*;
+    use std::convert::TryFrom;
+
+    fn assert_send<T: Send>() {}
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn test_state_is_thread_safe() {
+        assert_send::<State>();
+        assert_sync::<State>();
+        assert_send::<Calculator<Sender<DisplayEvent>>>();
+        assert_send::<Calculator<NoDisplay>>();
+        assert_sync::<Calculator<NoDisplay>>();
+    }
+
+    #[test]
+    fn test_calculator_thread() {
+        let worker = spawn_calculator(State::default());
+        for button in [SIX_BUTTON, DIVIDE_BUTTON, FOUR_BUTTON, EQUALS_BUTTON].iter() {
+            worker.keys.send(Key::try_from(*button).unwrap()).unwrap();
+        }
+        drop(worker.keys);
+        let state = worker.handle.join().unwrap();
+        assert_eq!(state.variable_values.get(Variable::Ans), 1.5);
+
+        let events: Vec<DisplayEvent> = worker.events.iter().collect();
+        assert!(events.contains(&DisplayEvent::EntryChanged("6/4".to_string())));
+        assert!(events.contains(&DisplayEvent::HistoryChanged("6/4".to_string())));
+        assert_eq!(
+            events.last(),
+            Some(&DisplayEvent::VariableChanged(Variable::Ans, 1.5))
+        );
+    }
+}
@@ -0,0 +1,141 @@
+/* Drives the calculator through the C interface, run it with `make` in this directory. */
+#include <math.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "avicalc.h"
+
+struct screen {
+    char entry[256];
+    size_t cursor;
+    char previous_entry[256];
+    double ans;
+    int errors;
+    size_t error_index;
+    AvicalcAngleMode angle_mode;
+    AvicalcDisplayMode display_mode;
+    size_t tape_length;
+    double last_result;
+    char formula[256];
+};
+
+static void entry_changed(void *user_data, const char *entry) {
+    struct screen *screen = user_data;
+    strncpy(screen->entry, entry, sizeof(screen->entry) - 1);
+}
+
+static void cursor_moved(void *user_data, size_t position) {
+    struct screen *screen = user_data;
+    screen->cursor = position;
+}
+
+static void history_changed(void *user_data, const char *previous_entry) {
+    struct screen *screen = user_data;
+    strncpy(screen->previous_entry, previous_entry, sizeof(screen->previous_entry) - 1);
+}
+
+static void variable_changed(void *user_data, int32_t variable, double value) {
+    struct screen *screen = user_data;
+    if (variable == AVICALC_VARIABLE_ANS) {
+        screen->ans = value;
+    }
+}
+
+static void error(void *user_data, AvicalcErrorKind kind, size_t token_index) {
+    struct screen *screen = user_data;
+    if (kind == AVICALC_ERROR_KIND_DIVISION_BY_ZERO) {
+        screen->errors++;
+        screen->error_index = token_index;
+    }
+}
+
+static void angle_mode_changed(void *user_data, AvicalcAngleMode mode) {
+    struct screen *screen = user_data;
+    screen->angle_mode = mode;
+}
+
+static void display_mode_changed(void *user_data, AvicalcDisplayMode mode) {
+    struct screen *screen = user_data;
+    screen->display_mode = mode;
+}
+
+static void tape_changed(void *user_data, size_t length, const char *last_expression,
+                         double last_result) {
+    struct screen *screen = user_data;
+    (void)last_expression;
+    screen->tape_length = length;
+    screen->last_result = last_result;
+}
+
+static void formula_changed(void *user_data, int32_t variable, const char *formula) {
+    struct screen *screen = user_data;
+    if (variable == AVICALC_VARIABLE_A) {
+        strncpy(screen->formula, formula != NULL ? formula : "", sizeof(screen->formula) - 1);
+    }
+}
+
+#define CHECK(condition)                                              \
+    if (!(condition)) {                                               \
+        fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #condition); \
+        return 1;                                                     \
+    }
+
+int main(void) {
+    struct screen screen;
+    memset(&screen, 0, sizeof(screen));
+    AvicalcCallbacks callbacks = {
+        .user_data = &screen,
+        .entry_changed = entry_changed,
+        .history_changed = history_changed,
+        .variable_changed = variable_changed,
+        .error = error,
+        .cursor_moved = cursor_moved,
+        .angle_mode_changed = angle_mode_changed,
+        .display_mode_changed = display_mode_changed,
+        .tape_changed = tape_changed,
+        .formula_changed = formula_changed,
+        .named_variable_changed = NULL,
+    };
+
+    AvicalcCalculator *calculator = avicalc_new();
+    avicalc_set_callbacks(calculator, callbacks);
+
+    CHECK(avicalc_press_button(calculator, ONE_BUTTON) == 0);
+    CHECK(avicalc_press_button(calculator, TWO_BUTTON) == 0);
+    CHECK(avicalc_press_button(calculator, MULTIPLY_BUTTON) == 0);
+    CHECK(avicalc_press_button(calculator, THREE_BUTTON) == 0);
+    CHECK(strcmp(screen.entry, "12*3") == 0);
+    CHECK(screen.cursor == 4);
+    CHECK(avicalc_press_button(calculator, EQUALS_BUTTON) == 0);
+    CHECK(strcmp(screen.entry, "") == 0);
+    CHECK(strcmp(screen.previous_entry, "12*3") == 0);
+    CHECK(screen.ans == 36.);
+    CHECK(screen.tape_length == 1 && screen.last_result == 36.);
+
+    CHECK(avicalc_press_button(calculator, FIVE_BUTTON) == 0);
+    CHECK(avicalc_press_button(calculator, CALIBRATED_AIRSPEED_ASSIGN_BUTTON) == 0);
+    CHECK(avicalc_get_variable(calculator, AVICALC_VARIABLE_CAS) == 5.);
+
+    CHECK(avicalc_press_button(calculator, TWO_BUTTON) == 0);
+    CHECK(avicalc_press_button(calculator, A_FORMULA_BUTTON) == 0);
+    CHECK(strcmp(screen.formula, "2") == 0);
+
+    CHECK(avicalc_press_button(calculator, ONE_BUTTON) == 0);
+    CHECK(avicalc_press_button(calculator, DIVIDE_BUTTON) == 0);
+    CHECK(avicalc_press_button(calculator, ZERO_BUTTON) == 0);
+    CHECK(avicalc_press_button(calculator, EQUALS_BUTTON) == 0);
+    CHECK(screen.errors == 1);
+    CHECK(screen.error_index == 1);
+
+    CHECK(avicalc_press_button(calculator, ANGLE_MODE_BUTTON) == 0);
+    CHECK(screen.angle_mode == AVICALC_ANGLE_MODE_RADIANS);
+    CHECK(avicalc_press_button(calculator, TIME_DISPLAY_BUTTON) == 0);
+    CHECK(screen.display_mode == AVICALC_DISPLAY_MODE_TIME);
+
+    CHECK(avicalc_press_button(calculator, 999) == -1);
+    CHECK(isnan(avicalc_get_variable(calculator, -1)));
+
+    avicalc_free(calculator);
+    printf("ok\n");
+    return 0;
+}
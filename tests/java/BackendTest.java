@@ -0,0 +1,127 @@
+import java.util.ArrayList;
+import java.util.List;
+
+import pro.veezard.avicalc.Backend;
+
+// Drives the calculator through the Java interface on a desktop JVM, run it with `make` in this
+// directory.
+public class BackendTest {
+    // Button numbers from src/consts.rs
+    static final int ZERO = 0, ONE = 1, TWO = 2, THREE = 3, FIVE = 5;
+    static final int PLUS = 11, DIVIDE = 14;
+    static final int CAS_ASSIGN = 117, A_FORMULA = 401, EQUALS = 1000, UNDO = 1003;
+
+    static class Screen implements Backend.Listener {
+        String entry = "";
+        int cursor = 0;
+        String previousEntry = "";
+        List<Integer> changedVariables = new ArrayList<>();
+        List<Integer> errors = new ArrayList<>();
+        int tapeLength = 0;
+        String lastCalculation = null;
+        String formulaOfA = null;
+
+        public void entryChanged(String entry) {
+            this.entry = entry;
+        }
+
+        public void cursorMoved(int position) {
+            cursor = position;
+        }
+
+        public void historyChanged(String previousEntry) {
+            this.previousEntry = previousEntry;
+        }
+
+        public void variableChanged(int variable, double value) {
+            changedVariables.add(variable);
+        }
+
+        public void error(String message, int tokenIndex) {
+            errors.add(tokenIndex);
+        }
+
+        public void tapeChanged(int length, String lastExpression, double lastResult) {
+            tapeLength = length;
+            lastCalculation = lastExpression + "=" + lastResult;
+        }
+
+        public void formulaChanged(int variable, String formula) {
+            if (variable == Backend.VARIABLE_A) {
+                formulaOfA = formula;
+            }
+        }
+    }
+
+    static void check(boolean condition, String what) {
+        if (!condition) {
+            throw new AssertionError("check failed: " + what);
+        }
+    }
+
+    public static void main(String[] args) {
+        System.loadLibrary("avicalc_backend");
+
+        Screen screen = new Screen();
+        String saved;
+        double ans;
+        try (Backend backend = new Backend(screen)) {
+            for (int button : new int[] {ONE, TWO, PLUS, THREE}) {
+                check(backend.keyPressed(button), "known button");
+            }
+            check(screen.entry.equals("12+3") && screen.cursor == 4, "entry field");
+            check(backend.keyPressed(EQUALS), "equals");
+            check(screen.entry.isEmpty() && screen.previousEntry.equals("12+3"), "history");
+            check(backend.getVariable(Backend.VARIABLE_ANS) == 15., "ans");
+            check(screen.tapeLength == 1 && screen.lastCalculation.equals("12+3=15.0"), "tape");
+
+            backend.keyPressed(FIVE);
+            backend.keyPressed(CAS_ASSIGN);
+            check(screen.changedVariables.contains(Backend.VARIABLE_CAS), "cas shown");
+
+            backend.keyPressed(TWO);
+            backend.keyPressed(A_FORMULA);
+            check("2".equals(screen.formulaOfA), "formula shown");
+
+            backend.keyPressed(ONE);
+            backend.keyPressed(DIVIDE);
+            backend.keyPressed(ZERO);
+            backend.keyPressed(EQUALS);
+            check(screen.errors.size() == 1 && screen.errors.get(0) == 1, "division by zero");
+
+            check(!backend.keyPressed(999), "unknown button");
+            check(Double.isNaN(backend.getVariable(-1)), "unknown variable");
+            saved = backend.saveState();
+            ans = backend.getVariable(Backend.VARIABLE_ANS);
+        }
+
+        Screen restoredScreen = new Screen();
+        try (Backend backend = new Backend(restoredScreen)) {
+            backend.restoreState(saved);
+            check(restoredScreen.entry.equals("1/0"), "restored entry field");
+            check(backend.getVariable(Backend.VARIABLE_CAS) == 5., "restored cas");
+            check(backend.getVariable(Backend.VARIABLE_ANS) == ans, "restored ans");
+            backend.keyPressed(UNDO);
+            check(restoredScreen.entry.equals("1/0"), "restore can't be undone");
+            try {
+                backend.restoreState("{}");
+                check(false, "exception expected");
+            } catch (IllegalArgumentException expected) {
+            }
+        }
+
+        // Exceptions of the listener reach the caller of keyPressed
+        Backend.Listener throwing = new Screen() {
+            public void entryChanged(String entry) {
+                throw new IllegalArgumentException("from listener");
+            }
+        };
+        try (Backend backend = new Backend(throwing)) {
+            backend.keyPressed(ONE);
+            check(false, "exception expected");
+        } catch (IllegalArgumentException expected) {
+        }
+
+        System.out.println("ok");
+    }
+}